@@ -0,0 +1,99 @@
+use std::env;
+use std::num::NonZeroU32;
+
+
+// the arguments of a day, flags and `--name value` options may come in any order
+#[derive(Debug, Clone)]
+pub struct Args {
+    args: Vec<String>
+}
+
+impl Args {
+    pub fn new(args: Vec<String>) -> Self {
+        Args { args }
+    }
+
+    // without the name of the binary
+    pub fn from_env() -> Self {
+        Args::new(env::args().skip(1).collect())
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(|x| x.as_str())
+    }
+
+    // the first argument unless it is a flag or an option
+    pub fn positional(&self) -> Option<&str> {
+        self.get(0).filter(|x| !x.starts_with("--"))
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.args.iter().any(|x| x == name)
+    }
+
+    // the value following the first `name`, panics if there is none
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.args.iter()
+            .position(|x| x == name)
+            .map(|index| self.get(index + 1).unwrap_or_else(|| panic!("{} needs a value", name)))
+    }
+
+    // the values of every `name`, for options which may be repeated
+    pub fn options(&self, name: &str) -> Vec<&str> {
+        self.args.iter()
+            .zip(self.args.iter().skip(1))
+            .filter(|(x, _)| *x == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    // `--fps N` of the animated days, panics for 0 which would make every frame last forever
+    pub fn frame_rate(&self) -> Option<NonZeroU32> {
+        self.option("--fps").map(|x| match x.parse::<u32>() {
+            Ok(0) => panic!("--fps needs a frame rate above 0"),
+            Ok(fps) => NonZeroU32::new(fps).unwrap(),
+            Err(_) => panic!("--fps needs a number")
+        })
+    }
+}
+
+
+#[cfg(test)]
+fn test_args(args: &str) -> Args {
+    Args::new(args.split_whitespace().map(|x| x.to_string()).collect())
+}
+
+#[test]
+fn test_options() {
+    let args = test_args("image.png --stats --color 3 --mode over --color 5");
+    assert_eq!(args.positional(), Some("image.png"));
+    assert_eq!(args.get(1), Some("--stats"));
+    assert!(args.flag("--stats"));
+    assert!(!args.flag("--heatmap"));
+    assert_eq!(args.option("--mode"), Some("over"));
+    assert_eq!(args.option("--color"), Some("3"));
+    assert_eq!(args.options("--color"), vec!["3", "5"]);
+    assert_eq!(args.option("--theme"), None);
+    assert!(args.options("--theme").is_empty());
+
+    assert_eq!(test_args("--stats").positional(), None);
+    assert_eq!(test_args("").positional(), None);
+}
+
+#[test]
+#[should_panic(expected = "--mode needs a value")]
+fn test_missing_value() {
+    test_args("--stats --mode").option("--mode");
+}
+
+#[test]
+fn test_frame_rate() {
+    assert_eq!(test_args("--watch").frame_rate(), None);
+    assert_eq!(test_args("--fps 30 --watch").frame_rate(), NonZeroU32::new(30));
+}
+
+#[test]
+#[should_panic(expected = "--fps needs a frame rate above 0")]
+fn test_zero_frame_rate() {
+    test_args("--fps 0").frame_rate();
+}
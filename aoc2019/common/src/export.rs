@@ -0,0 +1,254 @@
+use std::io;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use grid::{Bounds, Grid};
+
+
+pub type Rgb = (u8, u8, u8);
+
+pub const BLACK: Rgb = (0, 0, 0);
+pub const WHITE: Rgb = (255, 255, 255);
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ppm,
+    Pgm,
+    Png
+}
+
+impl Format {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(Format::Ppm),
+            "pgm" => Some(Format::Pgm),
+            "png" => Some(Format::Png),
+            _ => None
+        }
+    }
+}
+
+
+// colors for the tiles of a grid, unset and unknown tiles use the background
+#[derive(Debug, Clone)]
+pub struct Palette<T> {
+    colors: Vec<(T, Rgb)>,
+    background: Rgb
+}
+
+impl<T: PartialEq> Palette<T> {
+    pub fn new(background: Rgb) -> Self {
+        Palette { colors: Vec::new(), background }
+    }
+
+    pub fn with(mut self, tile: T, color: Rgb) -> Self {
+        self.colors.retain(|(t, _)| *t != tile);
+        self.colors.push((tile, color));
+        self
+    }
+
+    pub fn color(&self, tile: Option<&T>) -> Rgb {
+        tile.and_then(|tile| self.colors.iter().find(|(t, _)| t == tile))
+            .map(|(_, color)| *color)
+            .unwrap_or(self.background)
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>
+}
+
+impl Raster {
+    // y grows downwards, every tile becomes a `scale` x `scale` block of pixels
+    pub fn from_grid<T: PartialEq>(grid: &Grid<T>, palette: &Palette<T>, scale: usize) -> Self {
+        let bounds = grid.bounds().unwrap_or_else(|| Bounds::from_size(1, 1));
+        Raster::from_bounds(grid, bounds, palette, scale)
+    }
+
+    pub fn from_bounds<T: PartialEq>(grid: &Grid<T>, bounds: Bounds, palette: &Palette<T>, scale: usize) -> Self {
+        assert!(scale > 0, "scale has to be at least 1");
+
+        let width = bounds.width() as usize * scale;
+        let height = bounds.height() as usize * scale;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in bounds.min.1..=bounds.max.1 {
+            let row: Vec<Rgb> = (bounds.min.0..=bounds.max.0)
+                .flat_map(|x| {
+                    let color = palette.color(grid.get((x, y)));
+                    (0..scale).map(move |_| color)
+                })
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&row);
+            }
+        }
+
+        Raster { width, height, pixels }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[x + y * self.width]
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let format = Format::from_path(&path).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput, "unknown image format, expected ppm, pgm or png"
+        ))?;
+
+        let mut file = File::create(path)?;
+        self.write(&mut file, format)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W, format: Format) -> io::Result<()> {
+        match format {
+            Format::Ppm => self.write_ppm(writer),
+            Format::Pgm => self.write_pgm(writer),
+            Format::Png => self.write_png(writer)
+        }
+    }
+
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let data: Vec<u8> = self.pixels.iter().flat_map(|&(r, g, b)| vec![r, g, b]).collect();
+        writer.write_all(&data)
+    }
+
+    pub fn write_pgm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        let data: Vec<u8> = self.pixels.iter().map(|pixel| luma(*pixel)).collect();
+        writer.write_all(&data)
+    }
+
+    // 8 bit RGB, the image data is stored uncompressed
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // bit depth, color type, compression, filter, interlace
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_png_chunk(writer, b"IHDR", &header)?;
+
+        let mut scanlines = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width) {
+            // filter type none
+            scanlines.push(0);
+            for &(r, g, b) in row {
+                scanlines.extend_from_slice(&[r, g, b]);
+            }
+        }
+        write_png_chunk(writer, b"IDAT", &zlib_stored(&scanlines))?;
+
+        write_png_chunk(writer, b"IEND", &[])
+    }
+}
+
+
+fn luma(pixel: Rgb) -> u8 {
+    let (r, g, b) = (pixel.0 as u32, pixel.1 as u32, pixel.2 as u32);
+    ((r * 299 + g * 587 + b * 114) / 1000) as u8
+}
+
+fn write_png_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data.iter()));
+    writer.write_all(&crc.to_be_bytes())
+}
+
+// zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut result = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        result.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = if blocks.peek().is_none() { 1 } else { 0 };
+        let len = block.len() as u16;
+
+        result.push(last);
+        result.extend_from_slice(&len.to_le_bytes());
+        result.extend_from_slice(&(!len).to_le_bytes());
+        result.extend_from_slice(block);
+    }
+
+    result.extend_from_slice(&adler32(data).to_be_bytes());
+    result
+}
+
+fn crc32<'a, I: IntoIterator<Item=&'a u8>>(data: I) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+
+#[test]
+fn test_checksums() {
+    assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+}
+
+#[test]
+fn test_raster_export() {
+    let mut grid = Grid::new();
+    grid.insert((0, 0), 1);
+    grid.insert((1, 1), 0);
+
+    let palette = Palette::new((1, 2, 3)).with(1, WHITE).with(0, BLACK);
+    let raster = Raster::from_grid(&grid, &palette, 2);
+
+    assert_eq!((raster.width(), raster.height()), (4, 4));
+    assert_eq!(raster.pixel(1, 1), WHITE);
+    assert_eq!(raster.pixel(2, 0), (1, 2, 3));
+    assert_eq!(raster.pixel(3, 3), BLACK);
+
+    let mut ppm = Vec::new();
+    raster.write_ppm(&mut ppm).unwrap();
+    assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+    assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+
+    let mut pgm = Vec::new();
+    raster.write_pgm(&mut pgm).unwrap();
+    assert_eq!(&pgm[11..15], &[255, 255, 1, 1]);
+
+    let mut png = Vec::new();
+    raster.write_png(&mut png).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"));
+    assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+}
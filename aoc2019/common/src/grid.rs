@@ -0,0 +1,698 @@
+use std::cmp;
+use std::fmt;
+use std::str;
+use std::iter;
+use std::vec;
+use std::collections::HashMap;
+use std::collections::hash_map;
+
+
+pub type Position = (i32, i32);
+
+pub static NEIGHBOURS_4: [Position; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+pub static NEIGHBOURS_8: [Position; 8] = [
+    (-1, -1), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)
+];
+
+pub fn offset(position: Position, delta: Position) -> Position {
+    (position.0 + delta.0, position.1 + delta.1)
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Position,
+    pub max: Position
+}
+
+impl Bounds {
+    pub fn new(min: Position, max: Position) -> Self {
+        Bounds { min, max }
+    }
+
+    pub fn from_position(position: Position) -> Self {
+        Bounds::new(position, position)
+    }
+
+    pub fn from_size(width: i32, height: i32) -> Self {
+        assert!(width > 0 && height > 0, "bounds need a size of at least 1x1");
+        Bounds::new((0, 0), (width - 1, height - 1))
+    }
+
+    pub fn extend(&self, position: Position) -> Self {
+        Bounds::new(
+            (cmp::min(self.min.0, position.0), cmp::min(self.min.1, position.1)),
+            (cmp::max(self.max.0, position.0), cmp::max(self.max.1, position.1))
+        )
+    }
+
+    // bounds are inclusive, a single position has a width and height of 1
+    pub fn width(&self) -> i32 {
+        self.max.0 - self.min.0 + 1
+    }
+
+    pub fn height(&self) -> i32 {
+        self.max.1 - self.min.1 + 1
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() as usize * self.height() as usize
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.0 >= self.min.0 && position.0 <= self.max.0
+            && position.1 >= self.min.1 && position.1 <= self.max.1
+    }
+
+    // row major, starting at `min`
+    pub fn positions(&self) -> impl Iterator<Item=Position> {
+        let (min, max) = (self.min, self.max);
+        (min.1..=max.1).flat_map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+    }
+
+    fn index(&self, position: Position) -> usize {
+        (position.0 - self.min.0) as usize
+            + (position.1 - self.min.1) as usize * self.width() as usize
+    }
+
+    fn position(&self, index: usize) -> Position {
+        let width = self.width() as usize;
+        (self.min.0 + (index % width) as i32, self.min.1 + (index / width) as i32)
+    }
+}
+
+
+// maps a tile to the character used when parsing or displaying a grid
+pub trait TileChar: Sized {
+    fn from_char(c: char) -> Option<Self>;
+    fn to_char(&self) -> char;
+}
+
+
+#[derive(Debug, Clone)]
+enum Storage<T> {
+    Sparse(HashMap<Position, T>),
+    // grows whenever a tile outside of the bounds is inserted
+    Dense(Bounds, Vec<Option<T>>)
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Grid<T> {
+    storage: Storage<T>
+}
+
+impl<T> Grid<T> {
+    pub fn new() -> Self {
+        Grid::sparse()
+    }
+
+    pub fn sparse() -> Self {
+        Grid { storage: Storage::Sparse(HashMap::new()) }
+    }
+
+    pub fn dense(bounds: Bounds) -> Self {
+        let data = (0..bounds.area()).map(|_| None).collect();
+        Grid { storage: Storage::Dense(bounds, data) }
+    }
+
+    // an empty grid with the same backend as `self`
+    fn empty_like<U>(&self) -> Grid<U> {
+        match self.storage {
+            Storage::Sparse(_) => Grid::sparse(),
+            Storage::Dense(bounds, _) => Grid::dense(bounds)
+        }
+    }
+
+    // rows are stored top to bottom, the first row has y = 0
+    pub fn from_rows<R, I>(rows: R) -> Self
+        where R: IntoIterator<Item=I>,
+              I: IntoIterator<Item=T>
+    {
+        let mut grid = Grid::sparse();
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, tile) in row.into_iter().enumerate() {
+                grid.insert((x as i32, y as i32), tile);
+            }
+        }
+        grid.into_dense()
+    }
+
+    // same layout as `from_rows`, characters mapped to None are left unset
+    pub fn parse<F>(text: &str, mut tile: F) -> Self
+        where F: FnMut(char) -> Option<T>
+    {
+        let mut grid = Grid::sparse();
+        for (y, line) in text.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if let Some(tile) = tile(c) {
+                    grid.insert((x as i32, y as i32), tile);
+                }
+            }
+        }
+        grid.into_dense()
+    }
+
+    pub fn is_dense(&self) -> bool {
+        match self.storage {
+            Storage::Sparse(_) => false,
+            Storage::Dense(_, _) => true
+        }
+    }
+
+    pub fn into_dense(self) -> Self {
+        if self.is_dense() {
+            return self;
+        }
+        let mut grid = match self.bounds() {
+            Some(bounds) => Grid::dense(bounds),
+            None => return self
+        };
+        for (position, tile) in self {
+            grid.insert(position, tile);
+        }
+        grid
+    }
+
+    pub fn into_sparse(self) -> Self {
+        if self.is_dense() {
+            Grid { storage: Storage::Sparse(self.into_iter().collect()) }
+        } else {
+            self
+        }
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        match self.storage {
+            Storage::Sparse(ref tiles) => tiles.get(&position),
+            Storage::Dense(ref bounds, ref data) => {
+                if bounds.contains(position) {
+                    data[bounds.index(position)].as_ref()
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        match self.storage {
+            Storage::Sparse(ref mut tiles) => tiles.get_mut(&position),
+            Storage::Dense(ref bounds, ref mut data) => {
+                if bounds.contains(position) {
+                    data[bounds.index(position)].as_mut()
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    pub fn insert(&mut self, position: Position, tile: T) -> Option<T> {
+        if let Storage::Dense(bounds, _) = self.storage {
+            if !bounds.contains(position) {
+                self.grow(bounds.extend(position));
+            }
+        }
+
+        match self.storage {
+            Storage::Sparse(ref mut tiles) => tiles.insert(position, tile),
+            Storage::Dense(ref bounds, ref mut data) => {
+                data[bounds.index(position)].replace(tile)
+            }
+        }
+    }
+
+    pub fn get_or_insert_with<F>(&mut self, position: Position, tile: F) -> &mut T
+        where F: FnOnce() -> T
+    {
+        if !self.contains(position) {
+            self.insert(position, tile());
+        }
+        self.get_mut(position).unwrap()
+    }
+
+    pub fn remove(&mut self, position: Position) -> Option<T> {
+        match self.storage {
+            Storage::Sparse(ref mut tiles) => tiles.remove(&position),
+            Storage::Dense(ref bounds, ref mut data) => {
+                if bounds.contains(position) {
+                    data[bounds.index(position)].take()
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn grow(&mut self, new_bounds: Bounds) {
+        if let Storage::Dense(ref mut bounds, ref mut data) = self.storage {
+            let mut new_data: Vec<Option<T>> = (0..new_bounds.area()).map(|_| None).collect();
+            for (position, tile) in bounds.positions().zip(data.drain(..)) {
+                new_data[new_bounds.index(position)] = tile;
+            }
+            *bounds = new_bounds;
+            *data = new_data;
+        }
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        self.get(position).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        match self.storage {
+            Storage::Sparse(ref tiles) => tiles.len(),
+            Storage::Dense(_, ref data) => data.iter().filter(|x| x.is_some()).count()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // sparse grids iterate in arbitrary order, dense grids row by row
+    pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item=(Position, &'a T)> + 'a> {
+        match self.storage {
+            Storage::Sparse(ref tiles) => Box::new(
+                tiles.iter().map(|(position, tile)| (*position, tile))
+            ),
+            Storage::Dense(ref bounds, ref data) => Box::new(
+                bounds.positions()
+                    .zip(data.iter())
+                    .filter_map(|(position, tile)| tile.as_ref().map(|tile| (position, tile)))
+            )
+        }
+    }
+
+    pub fn positions<'a>(&'a self) -> impl Iterator<Item=Position> + 'a {
+        self.iter().map(|(position, _)| position)
+    }
+
+    // smallest box containing all set tiles, None for an empty grid
+    pub fn bounds(&self) -> Option<Bounds> {
+        let mut positions = self.positions();
+        let first = positions.next()?;
+
+        Some(positions.fold(Bounds::from_position(first), |bounds, position| bounds.extend(position)))
+    }
+
+    pub fn neighbours4<'a>(&'a self, position: Position) -> impl Iterator<Item=(Position, &'a T)> + 'a {
+        self.neighbours(position, &NEIGHBOURS_4)
+    }
+
+    pub fn neighbours8<'a>(&'a self, position: Position) -> impl Iterator<Item=(Position, &'a T)> + 'a {
+        self.neighbours(position, &NEIGHBOURS_8)
+    }
+
+    fn neighbours<'a>(&'a self, position: Position, deltas: &'static [Position])
+        -> impl Iterator<Item=(Position, &'a T)> + 'a
+    {
+        deltas.iter()
+            .map(move |delta| offset(position, *delta))
+            .filter_map(move |position| self.get(position).map(|tile| (position, tile)))
+    }
+
+    // a whole row or column within the grid's bounds, including unset tiles
+    pub fn row<'a>(&'a self, y: i32) -> impl Iterator<Item=Option<&'a T>> + 'a {
+        let (min, max) = self.bounds().map(|b| (b.min.0, b.max.0)).unwrap_or((0, -1));
+        (min..=max).map(move |x| self.get((x, y)))
+    }
+
+    pub fn column<'a>(&'a self, x: i32) -> impl Iterator<Item=Option<&'a T>> + 'a {
+        let (min, max) = self.bounds().map(|b| (b.min.1, b.max.1)).unwrap_or((0, -1));
+        (min..=max).map(move |y| self.get((x, y)))
+    }
+
+    pub fn map<U, F>(&self, mut f: F) -> Grid<U>
+        where F: FnMut(&T) -> U
+    {
+        let mut grid = self.empty_like();
+        for (position, tile) in self.iter() {
+            grid.insert(position, f(tile));
+        }
+        grid
+    }
+
+    // moves every tile to `f(position, bounds)`, with the bounds of all set tiles
+    fn transform<F>(self, f: F) -> Self
+        where F: Fn(Position, Bounds) -> Position
+    {
+        let bounds = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return self
+        };
+
+        let mut grid = self.empty_like();
+        for (position, tile) in self {
+            grid.insert(f(position, bounds), tile);
+        }
+        grid
+    }
+
+    pub fn flip_horizontal(self) -> Self {
+        self.transform(|(x, y), b| (b.min.0 + b.max.0 - x, y))
+    }
+
+    pub fn flip_vertical(self) -> Self {
+        self.transform(|(x, y), b| (x, b.min.1 + b.max.1 - y))
+    }
+
+    // clockwise when rendered with y growing downwards, keeps the top left corner
+    pub fn rotate_right(self) -> Self {
+        self.transform(|(x, y), b| (b.min.0 + b.max.1 - y, b.min.1 + x - b.min.0))
+    }
+
+    pub fn rotate_left(self) -> Self {
+        self.transform(|(x, y), b| (b.min.0 + y - b.min.1, b.min.1 + b.max.0 - x))
+    }
+
+    // one line per row, y growing downwards, unset tiles are passed as None
+    pub fn render<F>(&self, tile: F) -> String
+        where F: FnMut(Option<&T>) -> char
+    {
+        self.renderer().render(tile)
+    }
+
+    pub fn renderer(&self) -> Renderer<'_, T> {
+        Renderer::new(self)
+    }
+}
+
+// direction in which y grows when a grid is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YAxis {
+    // the first row has the smallest y, like screens and the puzzle inputs
+    Down,
+    // the first row has the largest y, north is up
+    Up
+}
+
+
+// draws a grid as text, by default within its exact bounds and with y growing downwards
+pub struct Renderer<'a, T> {
+    grid: &'a Grid<T>,
+    y_axis: YAxis,
+    bounds: Option<Bounds>,
+    // positions which are drawn even if they are not part of the grid
+    include: Vec<Position>,
+    axes: bool,
+    highlights: Vec<(Position, char)>,
+    line_ending: &'static str
+}
+
+impl<'a, T> Renderer<'a, T> {
+    pub fn new(grid: &'a Grid<T>) -> Self {
+        Renderer {
+            grid,
+            y_axis: YAxis::Down,
+            bounds: None,
+            include: Vec::new(),
+            axes: false,
+            highlights: Vec::new(),
+            line_ending: "\n"
+        }
+    }
+
+    pub fn y_axis(mut self, y_axis: YAxis) -> Self {
+        self.y_axis = y_axis;
+        self
+    }
+
+    // draws exactly these bounds, tiles and highlights outside of them are cut off
+    pub fn bounds(mut self, bounds: Bounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    // grows the bounds of the grid to contain `position`, e.g. the origin
+    pub fn include(mut self, position: Position) -> Self {
+        self.include.push(position);
+        self
+    }
+
+    // x coordinates written vertically above the columns and y coordinates in front of the rows
+    pub fn axes(mut self, axes: bool) -> Self {
+        self.axes = axes;
+        self
+    }
+
+    // drawn instead of the tile, later highlights win, the bounds grow to contain it
+    pub fn highlight(mut self, position: Position, c: char) -> Self {
+        self.highlights.push((position, c));
+        self
+    }
+
+    pub fn line_ending(mut self, line_ending: &'static str) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    // None if there is nothing to draw
+    pub fn visible_bounds(&self) -> Option<Bounds> {
+        if self.bounds.is_some() {
+            return self.bounds;
+        }
+
+        let extra = self.include.iter().chain(self.highlights.iter().map(|(position, _)| position));
+        extra.fold(self.grid.bounds(), |bounds, position| Some(match bounds {
+            Some(bounds) => bounds.extend(*position),
+            None => Bounds::from_position(*position)
+        }))
+    }
+
+    // unset tiles are passed as None
+    pub fn render<F>(&self, mut tile: F) -> String
+        where F: FnMut(Option<&T>) -> char
+    {
+        let bounds = match self.visible_bounds() {
+            Some(bounds) => bounds,
+            None => return String::new()
+        };
+
+        let rows: Vec<i32> = match self.y_axis {
+            YAxis::Down => (bounds.min.1..=bounds.max.1).collect(),
+            YAxis::Up => (bounds.min.1..=bounds.max.1).rev().collect()
+        };
+
+        let mut lines = Vec::with_capacity(rows.len());
+        let label_width = rows.iter().map(|y| y.to_string().len()).max().unwrap_or(0);
+        if self.axes {
+            let labels: Vec<Vec<char>> = (bounds.min.0..=bounds.max.0)
+                .map(|x| x.to_string().chars().collect())
+                .collect();
+            let label_height = labels.iter().map(|label| label.len()).max().unwrap_or(0);
+
+            // labels are aligned at the bottom, right above the first row
+            for line in 0..label_height {
+                let header: String = labels.iter()
+                    .map(|label| {
+                        let skip = label_height - label.len();
+                        if line < skip { ' ' } else { label[line - skip] }
+                    })
+                    .collect();
+                lines.push(format!("{:width$} {}", "", header, width = label_width));
+            }
+        }
+
+        for y in rows {
+            let row: String = (bounds.min.0..=bounds.max.0)
+                .map(|x| {
+                    let highlight = self.highlights.iter().rev().find(|(position, _)| *position == (x, y));
+                    match highlight {
+                        Some((_, c)) => *c,
+                        None => tile(self.grid.get((x, y)))
+                    }
+                })
+                .collect();
+
+            if self.axes {
+                lines.push(format!("{:>width$} {}", y, row, width = label_width));
+            } else {
+                lines.push(row);
+            }
+        }
+
+        lines.join(self.line_ending)
+    }
+}
+
+
+impl<T> Default for Grid<T> {
+    fn default() -> Self {
+        Grid::new()
+    }
+}
+
+impl<T: PartialEq> Grid<T> {
+    pub fn positions_of<'a>(&'a self, tile: T) -> impl Iterator<Item=Position> + 'a {
+        self.iter()
+            .filter(move |(_, v)| **v == tile)
+            .map(|(k, _)| k)
+    }
+}
+
+impl<T> IntoIterator for Grid<T> {
+    type Item = (Position, T);
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self.storage {
+            Storage::Sparse(tiles) => IntoIter::Sparse(tiles.into_iter()),
+            Storage::Dense(bounds, data) => IntoIter::Dense(bounds, data.into_iter().enumerate())
+        }
+    }
+}
+
+pub enum IntoIter<T> {
+    Sparse(hash_map::IntoIter<Position, T>),
+    Dense(Bounds, iter::Enumerate<vec::IntoIter<Option<T>>>)
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (Position, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            IntoIter::Sparse(ref mut tiles) => tiles.next(),
+            IntoIter::Dense(ref bounds, ref mut data) => loop {
+                if let (index, Some(tile)) = data.next()? {
+                    break Some((bounds.position(index), tile));
+                }
+            }
+        }
+    }
+}
+
+impl<T: TileChar> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(|tile| tile.map(|t| t.to_char()).unwrap_or(' ')))
+    }
+}
+
+impl<T: TileChar> str::FromStr for Grid<T> {
+    // position and character of the first unknown tile
+    type Err = (Position, char);
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut grid = Grid::sparse();
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let position = (x as i32, y as i32);
+                grid.insert(position, T::from_char(c).ok_or((position, c))?);
+            }
+        }
+        Ok(grid.into_dense())
+    }
+}
+
+
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TestTile(char);
+
+#[cfg(test)]
+impl TileChar for TestTile {
+    fn from_char(c: char) -> Option<Self> {
+        if c == '?' { None } else { Some(TestTile(c)) }
+    }
+
+    fn to_char(&self) -> char {
+        self.0
+    }
+}
+
+#[test]
+fn test_grid_backends() {
+    let mut sparse = Grid::sparse();
+    let mut dense = Grid::dense(Bounds::from_size(2, 2));
+
+    for grid in &mut [&mut sparse, &mut dense] {
+        assert_eq!(grid.insert((1, 1), 'a'), None);
+        assert_eq!(grid.insert((-2, 3), 'b'), None);
+        assert_eq!(grid.insert((1, 1), 'c'), Some('a'));
+
+        assert_eq!(grid.get((1, 1)), Some(&'c'));
+        assert_eq!(grid.get((0, 0)), None);
+        assert_eq!(grid.get((100, 100)), None);
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.bounds(), Some(Bounds::new((-2, 1), (1, 3))));
+
+        assert_eq!(grid.remove((-2, 3)), Some('b'));
+        assert_eq!(grid.bounds(), Some(Bounds::from_position((1, 1))));
+    }
+    assert!(dense.is_dense());
+    assert!(!sparse.is_dense());
+}
+
+#[test]
+fn test_grid_parse_and_display() {
+    let text = "ab\ncd\nef";
+    let grid: Grid<TestTile> = text.parse().unwrap();
+
+    assert_eq!(grid.bounds(), Some(Bounds::from_size(2, 3)));
+    assert_eq!(grid.get((1, 2)), Some(&TestTile('f')));
+    assert_eq!(grid.to_string(), text);
+    assert_eq!("ab\nc?".parse::<Grid<TestTile>>().unwrap_err(), ((1, 1), '?'));
+}
+
+#[test]
+fn test_grid_rows_and_neighbours() {
+    let grid = Grid::parse("#.#\n.#.\n#..", |c| if c == '#' { Some(true) } else { None });
+
+    assert_eq!(grid.row(0).collect::<Vec<_>>(), vec![Some(&true), None, Some(&true)]);
+    assert_eq!(grid.column(2).collect::<Vec<_>>(), vec![Some(&true), None, None]);
+    assert_eq!(grid.neighbours4((1, 1)).count(), 0);
+    assert_eq!(grid.neighbours8((1, 1)).count(), 3);
+    assert_eq!(grid.neighbours8((0, 1)).map(|(p, _)| p).collect::<Vec<_>>(), vec![(0, 0), (1, 1), (0, 2)]);
+}
+
+#[test]
+fn test_grid_transform() {
+    let grid: Grid<TestTile> = "abc\ndef".parse().unwrap();
+
+    assert_eq!(grid.clone().flip_horizontal().to_string(), "cba\nfed");
+    assert_eq!(grid.clone().flip_vertical().to_string(), "def\nabc");
+    assert_eq!(grid.clone().rotate_right().to_string(), "da\neb\nfc");
+    assert_eq!(grid.clone().rotate_left().to_string(), "cf\nbe\nad");
+    assert_eq!(grid.clone().rotate_left().rotate_right().to_string(), grid.to_string());
+    assert_eq!(grid.clone().into_sparse().rotate_right().to_string(), "da\neb\nfc");
+}
+
+#[test]
+fn test_renderer_bounds() {
+    let mut grid = Grid::new();
+    grid.insert((3, 5), '#');
+    grid.insert((5, 6), '#');
+    let tile = |tile: Option<&char>| tile.cloned().unwrap_or('.');
+
+    // exact bounds, the origin is not part of them and the last column is included
+    assert_eq!(grid.renderer().render(tile), "#..\n..#");
+    assert_eq!(grid.renderer().y_axis(YAxis::Up).render(tile), "..#\n#..");
+    assert_eq!(grid.renderer().include((6, 5)).render(tile), "#...\n..#.");
+    assert_eq!(grid.renderer().bounds(Bounds::from_position((5, 6))).render(tile), "#");
+    assert_eq!(grid.renderer().bounds(Bounds::new((4, 5), (5, 5))).render(tile), "..");
+
+    let single = Grid::parse("x", Some);
+    assert_eq!(single.renderer().render(tile), "x");
+    assert_eq!(Grid::<char>::new().renderer().render(tile), "");
+    assert_eq!(Grid::<char>::new().renderer().include((0, 0)).render(tile), ".");
+}
+
+#[test]
+fn test_renderer_overlays() {
+    let grid = Grid::parse("ab\ncd", Some);
+    let tile = |tile: Option<&char>| tile.cloned().unwrap_or('.');
+
+    let renderer = grid.renderer().highlight((1, 0), 'R').highlight((1, 0), 'S').highlight((-1, 1), 'O');
+    assert_eq!(renderer.render(tile), ".aS\nOcd");
+    assert_eq!(renderer.y_axis(YAxis::Up).line_ending("\r\n").render(tile), "Ocd\r\n.aS");
+
+    let grid = Grid::parse("ab\ncd", Some).flip_vertical().into_sparse();
+    let mut moved = Grid::new();
+    for (position, tile) in grid {
+        moved.insert((position.0 + 9, position.1 - 1), tile);
+    }
+    assert_eq!(moved.renderer().axes(true).render(tile), "    1\n   90\n-1 cd\n 0 ab");
+    assert_eq!(moved.renderer().axes(true).y_axis(YAxis::Up).render(tile), "    1\n   90\n 0 ab\n-1 cd");
+}
@@ -0,0 +1,13 @@
+// the part of an intcode program the shared modules talk to, every day still brings its own machine
+pub trait IoHandler {
+    fn input(&mut self) -> i64;
+    fn output(&mut self, value: i64);
+
+    fn done(&mut self) {
+    }
+
+    // checked after every instruction, pauses the program when true
+    fn wants_pause(&self) -> bool {
+        false
+    }
+}
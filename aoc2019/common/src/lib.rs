@@ -0,0 +1,9 @@
+// code used by several days, each day depends on it with a path dependency
+
+pub mod args;
+pub mod grid;
+pub mod intcode;
+pub mod robot;
+pub mod export;
+pub mod ocr;
+pub mod pathfinding;
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use grid::{Bounds, Grid};
+
+
+// the block letters used by the puzzles, 6 rows high and trimmed to their lit columns
+static FONT: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', "###\n.#.\n.#.\n.#.\n.#.\n###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####")
+];
+
+pub const GLYPH_HEIGHT: i32 = 6;
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    Empty,
+    InvalidHeight(i32),
+    // position of the glyph in the text and the glyph itself, `#` is lit
+    UnknownGlyph(usize, String)
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Empty => write!(f, "nothing to recognize"),
+            OcrError::InvalidHeight(height) => write!(
+                f, "expected text with a height of {}, got {}", GLYPH_HEIGHT, height
+            ),
+            OcrError::UnknownGlyph(index, glyph) => write!(
+                f, "unknown glyph at position {}:\n{}", index, glyph
+            )
+        }
+    }
+}
+
+
+// splits the lit tiles (y growing downwards) into glyphs, separated by empty columns
+pub fn glyphs<T, F>(grid: &Grid<T>, lit: F) -> Result<Vec<String>, OcrError>
+    where F: Fn(&T) -> bool
+{
+    let is_lit = |position| grid.get(position).map(&lit).unwrap_or(false);
+
+    let bounds = grid.iter()
+        .filter(|(_, tile)| lit(tile))
+        .fold(None, |bounds: Option<Bounds>, (position, _)| Some(match bounds {
+            Some(bounds) => bounds.extend(position),
+            None => Bounds::from_position(position)
+        }))
+        .ok_or(OcrError::Empty)?;
+    if bounds.height() != GLYPH_HEIGHT {
+        return Err(OcrError::InvalidHeight(bounds.height()));
+    }
+
+    let mut glyphs = Vec::new();
+    let mut columns: Vec<String> = Vec::new();
+    for x in bounds.min.0..=bounds.max.0 + 1 {
+        let column: String = (bounds.min.1..=bounds.max.1)
+            .map(|y| if is_lit((x, y)) { '#' } else { '.' })
+            .collect();
+
+        if column.contains('#') {
+            columns.push(column);
+        } else if !columns.is_empty() {
+            glyphs.push(transpose(&columns));
+            columns.clear();
+        }
+    }
+
+    Ok(glyphs)
+}
+
+fn transpose(columns: &[String]) -> String {
+    let columns: Vec<Vec<char>> = columns.iter().map(|c| c.chars().collect()).collect();
+    (0..GLYPH_HEIGHT as usize)
+        .map(|y| columns.iter().map(|column| column[y]).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn recognize_glyph(glyph: &str) -> Option<char> {
+    FONT.iter()
+        .find(|(_, pattern)| *pattern == glyph)
+        .map(|(c, _)| *c)
+}
+
+pub fn recognize<T, F>(grid: &Grid<T>, lit: F) -> Result<String, OcrError>
+    where F: Fn(&T) -> bool
+{
+    glyphs(grid, lit)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, glyph)| recognize_glyph(&glyph).ok_or(OcrError::UnknownGlyph(index, glyph)))
+        .collect()
+}
+
+
+#[test]
+fn test_recognize() {
+    let text = "\
+        .##....##.####.#..#.###..\n\
+        #..#....#....#.#..#.#..#.\n\
+        #.......#...#..####.#..#.\n\
+        #.......#..#...#..#.###..\n\
+        #..#.#..#.#....#..#.#.#..\n\
+        .##...##..####.#..#.#..#.";
+    let grid = Grid::parse(text, |c| Some(c == '#'));
+
+    assert_eq!(recognize(&grid, |x| *x), Ok("CJZHR".to_string()));
+}
+
+#[test]
+fn test_recognize_errors() {
+    let grid: Grid<bool> = Grid::new();
+    assert_eq!(recognize(&grid, |x| *x), Err(OcrError::Empty));
+
+    let grid = Grid::parse("#\n#", |c| Some(c == '#'));
+    assert_eq!(recognize(&grid, |x| *x), Err(OcrError::InvalidHeight(2)));
+
+    let grid = Grid::parse("#..#..###\n#..#...#.\n####...#.\n#..#...#.\n#..#...#.\n#..#..#.#", |c| Some(c == '#'));
+    assert_eq!(
+        recognize(&grid, |x| *x),
+        Err(OcrError::UnknownGlyph(1, "###\n.#.\n.#.\n.#.\n.#.\n#.#".to_string()))
+    );
+}
@@ -0,0 +1,248 @@
+use std::hash::Hash;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+
+// nodes along a path including start and goal, together with the total cost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N> {
+    pub nodes: Vec<N>,
+    pub cost: u64
+}
+
+impl<N> Path<N> {
+    // amount of steps taken, one less than the amount of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+
+// distances of every reachable node to the closest source, with the node it was reached from
+#[derive(Debug, Clone)]
+pub struct Distances<N> {
+    distances: HashMap<N, u64>,
+    previous: HashMap<N, N>
+}
+
+impl<N: Clone + Eq + Hash> Distances<N> {
+    fn new() -> Self {
+        Distances { distances: HashMap::new(), previous: HashMap::new() }
+    }
+
+    pub fn get(&self, node: &N) -> Option<u64> {
+        self.distances.get(node).cloned()
+    }
+
+    pub fn contains(&self, node: &N) -> bool {
+        self.distances.contains_key(node)
+    }
+
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item=(&'a N, u64)> + 'a {
+        self.distances.iter().map(|(node, distance)| (node, *distance))
+    }
+
+    // node furthest away from the sources
+    pub fn furthest(&self) -> Option<(&N, u64)> {
+        self.iter().max_by_key(|(_, distance)| *distance)
+    }
+
+    // path from the closest source to `node`
+    pub fn path_to(&self, node: &N) -> Option<Path<N>> {
+        let cost = self.get(node)?;
+
+        let mut nodes = vec![node.clone()];
+        while let Some(previous) = self.previous.get(nodes.last().unwrap()) {
+            nodes.push(previous.clone());
+        }
+        nodes.reverse();
+
+        Some(Path { nodes, cost })
+    }
+}
+
+
+// every edge costs 1
+pub fn bfs<N, F, I>(start: N, neighbours: F) -> Distances<N>
+    where N: Clone + Eq + Hash, F: FnMut(&N) -> I, I: IntoIterator<Item=N>
+{
+    bfs_multi(vec![start], neighbours)
+}
+
+pub fn bfs_multi<N, S, F, I>(sources: S, mut neighbours: F) -> Distances<N>
+    where N: Clone + Eq + Hash, S: IntoIterator<Item=N>, F: FnMut(&N) -> I, I: IntoIterator<Item=N>
+{
+    let mut result = Distances::new();
+    let mut queue = VecDeque::new();
+
+    for source in sources {
+        if !result.contains(&source) {
+            result.distances.insert(source.clone(), 0);
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        let distance = result.distances[&node];
+        for next in neighbours(&node) {
+            if !result.contains(&next) {
+                result.distances.insert(next.clone(), distance + 1);
+                result.previous.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+
+    result
+}
+
+
+// neighbours come with the cost of the edge leading to them
+pub fn dijkstra<N, F, I>(start: N, neighbours: F) -> Distances<N>
+    where N: Clone + Eq + Hash, F: FnMut(&N) -> I, I: IntoIterator<Item=(N, u64)>
+{
+    search(start, None, neighbours, |_| 0)
+}
+
+// `heuristic` must never overestimate the remaining cost, otherwise the path may not be the shortest,
+// it doesn't have to be consistent as nodes are expanded again whenever they are reached cheaper
+pub fn astar<N, F, I, H>(start: N, goal: &N, neighbours: F, heuristic: H) -> Option<Path<N>>
+    where N: Clone + Eq + Hash, F: FnMut(&N) -> I, I: IntoIterator<Item=(N, u64)>, H: FnMut(&N) -> u64
+{
+    search(start, Some(goal), neighbours, heuristic).path_to(goal)
+}
+
+pub fn shortest_path<N, F, I>(start: N, goal: &N, neighbours: F) -> Option<Path<N>>
+    where N: Clone + Eq + Hash, F: FnMut(&N) -> I, I: IntoIterator<Item=(N, u64)>
+{
+    astar(start, goal, neighbours, |_| 0)
+}
+
+// distances between every pair of `nodes`, unreachable pairs are missing
+pub fn all_pairs<N, F, I>(nodes: &[N], mut neighbours: F) -> HashMap<(N, N), u64>
+    where N: Clone + Eq + Hash, F: FnMut(&N) -> I, I: IntoIterator<Item=(N, u64)>
+{
+    let mut result = HashMap::new();
+    for from in nodes {
+        let distances = dijkstra(from.clone(), &mut neighbours);
+        for to in nodes {
+            if let Some(distance) = distances.get(to) {
+                result.insert((from.clone(), to.clone()), distance);
+            }
+        }
+    }
+    result
+}
+
+// dijkstra ordered by cost + heuristic, stops early once `goal` is taken from the heap
+fn search<N, F, I, H>(start: N, goal: Option<&N>, mut neighbours: F, mut heuristic: H) -> Distances<N>
+    where N: Clone + Eq + Hash, F: FnMut(&N) -> I, I: IntoIterator<Item=(N, u64)>, H: FnMut(&N) -> u64
+{
+    let mut result = Distances::new();
+    // the heap only holds indices into `nodes`, so nodes don't have to be ordered
+    let mut nodes = Vec::new();
+    let mut heap = BinaryHeap::new();
+
+    result.distances.insert(start.clone(), 0);
+    heap.push(Reverse((heuristic(&start), 0, nodes.len())));
+    nodes.push(start);
+
+    while let Some(Reverse((_, cost, index))) = heap.pop() {
+        let node = nodes[index].clone();
+        // stale entry of a node which was reached cheaper in the meantime
+        if result.get(&node).map(|distance| cost > distance).unwrap_or(false) {
+            continue;
+        }
+        if goal == Some(&node) {
+            break;
+        }
+
+        for (next, weight) in neighbours(&node) {
+            let next_cost = cost + weight;
+            let better = result.get(&next).map(|distance| next_cost < distance).unwrap_or(true);
+            if better {
+                result.distances.insert(next.clone(), next_cost);
+                result.previous.insert(next.clone(), node.clone());
+                heap.push(Reverse((next_cost + heuristic(&next), next_cost, nodes.len())));
+                nodes.push(next);
+            }
+        }
+    }
+
+    result
+}
+
+
+#[cfg(test)]
+fn test_graph(node: &char) -> Vec<(char, u64)> {
+    // a - b - c - d with a shortcut a - d that is more expensive than the detour
+    match node {
+        'a' => vec![('b', 1), ('d', 5)],
+        'b' => vec![('a', 1), ('c', 1)],
+        'c' => vec![('b', 1), ('d', 1)],
+        'd' => vec![('c', 1), ('a', 5)],
+        _ => vec![]
+    }
+}
+
+#[test]
+fn test_bfs() {
+    let distances = bfs('a', |node| test_graph(node).into_iter().map(|(next, _)| next));
+    assert_eq!(distances.len(), 4);
+    assert_eq!(distances.get(&'c'), Some(2));
+    assert_eq!(distances.get(&'d'), Some(1));
+    assert_eq!(distances.furthest(), Some((&'c', 2)));
+    assert_eq!(distances.path_to(&'a'), Some(Path { nodes: vec!['a'], cost: 0 }));
+
+    let distances = bfs_multi(vec!['a', 'c'], |node| test_graph(node).into_iter().map(|(next, _)| next));
+    assert_eq!(distances.get(&'b'), Some(1));
+    assert_eq!(distances.get(&'d'), Some(1));
+}
+
+#[test]
+fn test_weighted_search() {
+    let distances = dijkstra('a', test_graph);
+    assert_eq!(distances.get(&'d'), Some(3));
+    assert_eq!(distances.path_to(&'d').unwrap().nodes, vec!['a', 'b', 'c', 'd']);
+    assert_eq!(distances.get(&'x'), None);
+
+    let path = astar('a', &'d', test_graph, |node| if *node == 'd' { 0 } else { 1 }).unwrap();
+    assert_eq!(path, Path { nodes: vec!['a', 'b', 'c', 'd'], cost: 3 });
+    assert_eq!(path.len(), 3);
+    assert_eq!(shortest_path('d', &'b', test_graph).map(|path| path.cost), Some(2));
+    assert_eq!(shortest_path('a', &'x', test_graph), None);
+
+    let pairs = all_pairs(&['a', 'c', 'x'], test_graph);
+    assert_eq!(pairs.get(&('a', 'c')), Some(&2));
+    assert_eq!(pairs.get(&('c', 'c')), Some(&0));
+    assert_eq!(pairs.get(&('x', 'x')), Some(&0));
+    assert_eq!(pairs.get(&('a', 'x')), None);
+}
+
+#[test]
+fn test_inconsistent_heuristic() {
+    // s - b - a - g costs 7, but a is first reached directly for 4
+    let graph = |node: &char| match node {
+        's' => vec![('a', 4), ('b', 1)],
+        'b' => vec![('a', 1)],
+        'a' => vec![('g', 5)],
+        _ => vec![]
+    };
+    // exact for b and never too high, but b - a drops it by more than the edge costs
+    let heuristic = |node: &char| if *node == 'b' { 6 } else { 0 };
+
+    let path = astar('s', &'g', graph, heuristic).unwrap();
+    assert_eq!(path, Path { nodes: vec!['s', 'b', 'a', 'g'], cost: 7 });
+}
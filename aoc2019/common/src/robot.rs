@@ -0,0 +1,254 @@
+use grid::{Grid, Position};
+use intcode::IoHandler;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Left,
+    Right,
+    Around,
+    Straight
+}
+
+impl Direction {
+    // clockwise, starting at north
+    pub fn all() -> impl Iterator<Item=Direction> {
+        static DIRECTIONS: [Direction; 4] = [
+            Direction::North, Direction::East, Direction::South, Direction::West
+        ];
+        DIRECTIONS.iter().cloned()
+    }
+
+    pub fn turn(&self, turn: Turn) -> Self {
+        match turn {
+            Turn::Left => self.turn_left(),
+            Turn::Right => self.turn_right(),
+            Turn::Around => self.reverse(),
+            Turn::Straight => *self
+        }
+    }
+
+    pub fn turn_left(&self) -> Self {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South
+        }
+    }
+
+    pub fn turn_right(&self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North
+        }
+    }
+
+    pub fn reverse(&self) -> Self {
+        self.turn_left().turn_left()
+    }
+
+    // y grows to the north
+    pub fn delta(&self) -> Position {
+        match self {
+            Direction::North => (0, 1),
+            Direction::East => (1, 0),
+            Direction::South => (0, -1),
+            Direction::West => (-1, 0)
+        }
+    }
+
+    pub fn add_delta(&self, position: Position) -> Position {
+        let delta = self.delta();
+        (position.0 + delta.0, position.1 + delta.1)
+    }
+}
+
+
+// a tile the intcode program can read or write as a single number
+pub trait Tile: Sized {
+    fn from_number(value: i64) -> Option<Self>;
+    fn to_number(&self) -> i64;
+}
+
+
+// collects outputs until a full instruction of `arity` values is available
+#[derive(Debug)]
+pub struct OutputDecoder {
+    arity: usize,
+    buffer: Vec<i64>
+}
+
+impl OutputDecoder {
+    pub fn new(arity: usize) -> Self {
+        assert!(arity > 0, "arity has to be at least 1");
+        OutputDecoder { arity, buffer: Vec::with_capacity(arity) }
+    }
+
+    pub fn push(&mut self, value: i64) -> Option<Vec<i64>> {
+        self.buffer.push(value);
+
+        if self.buffer.len() == self.arity {
+            Some(self.buffer.drain(..).collect())
+        } else {
+            None
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Robot<T> {
+    pub position: Position,
+    pub direction: Direction,
+    pub tiles: Grid<T>,
+    moves: usize
+}
+
+impl<T> Robot<T> {
+    pub fn new(position: Position, direction: Direction) -> Self {
+        Robot {
+            position,
+            direction,
+            tiles: Grid::new(),
+            moves: 0
+        }
+    }
+
+    pub fn current(&self) -> Option<&T> {
+        self.tiles.get(self.position)
+    }
+
+    pub fn paint(&mut self, tile: T) {
+        self.tiles.insert(self.position, tile);
+    }
+
+    pub fn turn(&mut self, turn: Turn) {
+        self.direction = self.direction.turn(turn);
+    }
+
+    // position in front of the robot
+    pub fn ahead(&self) -> Position {
+        self.direction.add_delta(self.position)
+    }
+
+    pub fn forward(&mut self) -> Position {
+        self.position = self.ahead();
+        self.moves += 1;
+        self.position
+    }
+
+    pub fn step(&mut self, direction: Direction) -> Position {
+        self.direction = direction;
+        self.forward()
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+}
+
+
+// puzzle specific behaviour of a robot, the io handler does the plumbing
+pub trait Controller<T> {
+    // amount of output values which make up a single instruction
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn input(&mut self, robot: &mut Robot<T>) -> i64;
+    fn output(&mut self, robot: &mut Robot<T>, values: &[i64]);
+
+    fn done(&mut self, _robot: &Robot<T>) {
+    }
+
+    // stops the program once the controller has all it needs
+    fn is_finished(&self, _robot: &Robot<T>) -> bool {
+        false
+    }
+}
+
+
+pub struct RobotIoHandler<T, C> {
+    robot: Robot<T>,
+    controller: C,
+    decoder: OutputDecoder
+}
+
+impl<T, C: Controller<T>> RobotIoHandler<T, C> {
+    pub fn new(robot: Robot<T>, controller: C) -> Self {
+        let decoder = OutputDecoder::new(controller.arity());
+
+        RobotIoHandler {
+            robot,
+            controller,
+            decoder
+        }
+    }
+
+    pub fn robot(&self) -> &Robot<T> {
+        &self.robot
+    }
+
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+}
+
+impl<T, C: Controller<T>> IoHandler for RobotIoHandler<T, C> {
+    fn input(&mut self) -> i64 {
+        self.controller.input(&mut self.robot)
+    }
+
+    fn output(&mut self, value: i64) {
+        if let Some(values) = self.decoder.push(value) {
+            self.controller.output(&mut self.robot, &values);
+        }
+    }
+
+    fn done(&mut self) {
+        self.controller.done(&self.robot);
+    }
+
+    fn wants_pause(&self) -> bool {
+        self.controller.is_finished(&self.robot)
+    }
+}
+
+
+#[test]
+fn test_direction_turns() {
+    for direction in Direction::all() {
+        assert_eq!(direction.turn_left().turn_right(), direction);
+        assert_eq!(direction.turn(Turn::Around).turn(Turn::Around), direction);
+        assert_eq!(direction.turn(Turn::Straight), direction);
+    }
+    assert_eq!(Direction::North.turn_right(), Direction::East);
+    assert_eq!(Direction::North.turn_left(), Direction::West);
+    assert_eq!(Direction::West.add_delta((0, 0)), (-1, 0));
+}
+
+#[test]
+fn test_output_decoder() {
+    let mut decoder = OutputDecoder::new(3);
+    assert_eq!(decoder.push(1), None);
+    assert_eq!(decoder.push(2), None);
+    assert!(decoder.is_pending());
+    assert_eq!(decoder.push(3), Some(vec![1, 2, 3]));
+    assert!(!decoder.is_pending());
+    assert_eq!(decoder.push(4), None);
+}
@@ -1,7 +1,16 @@
+extern crate common;
 use std::ops;
 use std::fs;
+use std::fmt;
 use std::collections::HashMap;
 
+mod segments;
+mod svg;
+
+use common::args::Args;
+use common::grid::Grid;
+use segments::Wires;
+
 pub type CableId = i32;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -16,12 +25,19 @@ impl Vector {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     Left(i32),
     Right(i32),
     Up(i32),
-    Down(i32)
+    Down(i32),
+    // diagonal moves, one step per cell
+    UpLeft(i32),
+    UpRight(i32),
+    DownLeft(i32),
+    DownRight(i32),
+    // moves without drawing anything
+    Jump(Vector)
 }
 
 
@@ -30,38 +46,56 @@ impl<'a, 'b> ops::Add<&'b Vector> for &'a Instruction {
 
     fn add(self, rhs: &'b Vector) -> Vector {
         match self {
-            Instruction::Left(amount) => Vector::new(rhs.x - amount, rhs.y),
-            Instruction::Right(amount) => Vector::new(rhs.x + amount, rhs.y),
-            Instruction::Up(amount) => Vector::new(rhs.x, rhs.y + amount),
-            Instruction::Down(amount) => Vector::new(rhs.x, rhs.y - amount)
+            Instruction::Jump(offset) => Vector::new(rhs.x + offset.x, rhs.y + offset.y),
+            _ => {
+                let (direction, amount) = (self.direction(), self.amount());
+                Vector::new(rhs.x + direction.x * amount, rhs.y + direction.y * amount)
+            }
         }
     }
 }
 
 impl Instruction {
-    fn coordinates(&self, from: Vector) -> Vec<Vector> {
-        let to = self + &from;
-        let mut result = Vec::new();
-
-        let x_mult = match self {
-            Instruction::Left(_) => -1,
-            Instruction::Right(_) => 1,
-            _ => 0
-        };
-        let y_mult = match self {
-            Instruction::Down(_) => -1,
-            Instruction::Up(_) => 1,
-            _ => 0
-        };
+    // unit step of a move, a jump has none
+    fn direction(&self) -> Vector {
+        match self {
+            Instruction::Left(_) => Vector::new(-1, 0),
+            Instruction::Right(_) => Vector::new(1, 0),
+            Instruction::Up(_) => Vector::new(0, 1),
+            Instruction::Down(_) => Vector::new(0, -1),
+            Instruction::UpLeft(_) => Vector::new(-1, 1),
+            Instruction::UpRight(_) => Vector::new(1, 1),
+            Instruction::DownLeft(_) => Vector::new(-1, -1),
+            Instruction::DownRight(_) => Vector::new(1, -1),
+            Instruction::Jump(_) => Vector::new(0, 0)
+        }
+    }
 
-        for x in 0..(to.x - from.x).abs() + 1 {
-            result.push(Vector::new(from.x + (x * x_mult), from.y));
+    // amount of steps, a jump takes none
+    fn amount(&self) -> i32 {
+        match *self {
+            Instruction::Left(amount) | Instruction::Right(amount)
+                | Instruction::Up(amount) | Instruction::Down(amount)
+                | Instruction::UpLeft(amount) | Instruction::UpRight(amount)
+                | Instruction::DownLeft(amount) | Instruction::DownRight(amount) => amount,
+            Instruction::Jump(_) => 0
         }
-        for y in 1..(to.y - from.y).abs() + 1 {
-            result.push(Vector::new(to.x, from.y + (y * y_mult)));
+    }
+
+    pub fn is_drawn(&self) -> bool {
+        !matches!(self, Instruction::Jump(_))
+    }
+
+    // every cell the cable passes, including `from`, a jump only passes its target
+    fn coordinates(&self, from: Vector) -> Vec<Vector> {
+        if !self.is_drawn() {
+            return vec![from, self + &from];
         }
 
-        result
+        let direction = self.direction();
+        (0..self.amount() + 1)
+            .map(|step| Vector::new(from.x + direction.x * step, from.y + direction.y * step))
+            .collect()
     }
 }
 
@@ -83,34 +117,116 @@ fn test_instruction_coordinates() {
         Instruction::Up(2).coordinates(Vector::new(2, -1)),
         vec![Vector::new(2, -1), Vector::new(2, 0), Vector::new(2, 1)]
     );
+    assert_eq!(
+        Instruction::DownRight(2).coordinates(Vector::new(0, 0)),
+        vec![Vector::new(0, 0), Vector::new(1, -1), Vector::new(2, -2)]
+    );
+    assert_eq!(
+        Instruction::Jump(Vector::new(5, -3)).coordinates(Vector::new(1, 1)),
+        vec![Vector::new(1, 1), Vector::new(6, -2)]
+    );
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    // byte offset of the instruction in the path, followed by the instruction
+    Empty(usize),
+    UnknownDirection(usize, String),
+    InvalidAmount(usize, String)
+}
+
+impl ParseError {
+    pub fn offset(&self) -> usize {
+        match *self {
+            ParseError::Empty(offset)
+                | ParseError::UnknownDirection(offset, _)
+                | ParseError::InvalidAmount(offset, _) => offset
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty(offset) => write!(f, "empty instruction at offset {}", offset),
+            ParseError::UnknownDirection(offset, instruction) => write!(
+                f, "unknown direction in {:?} at offset {}", instruction, offset
+            ),
+            ParseError::InvalidAmount(offset, instruction) => write!(
+                f, "invalid amount in {:?} at offset {}", instruction, offset
+            )
+        }
+    }
 }
 
 
 pub type Path = Vec<Instruction>;
 
-pub fn parse<I, T>(path: I) -> Path
-    where I: IntoIterator<Item = T>,
-          T: Into<String>
-{
+// comma separated instructions: `R`, `L`, `U`, `D` and the diagonals `NE`, `NW`, `SE`, `SW`
+// followed by the amount of steps, or `J` followed by `x:y` to jump without drawing
+pub fn parse(path: &str) -> Result<Path, ParseError> {
     let mut result = Vec::new();
 
-    for instruction in path.into_iter() {
-        let instruction = instruction.into();
-        let (direction, amount) = instruction.split_at(1);
-        let amount = amount.parse::<i32>().unwrap();
+    let mut offset = 0;
+    for instruction in path.split(',') {
+        let start = offset + instruction.len() - instruction.trim_start().len();
+        offset += instruction.len() + 1;
+        let instruction = instruction.trim();
+        if instruction.is_empty() {
+            return Err(ParseError::Empty(start));
+        }
+
+        let split = instruction.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(instruction.len());
+        let (direction, amount) = instruction.split_at(split);
+        let invalid_amount = || ParseError::InvalidAmount(start, instruction.to_string());
+
+        if direction == "J" {
+            let mut parts = amount.splitn(2, ':').map(|x| x.parse::<i32>());
+            let x = parts.next().and_then(|x| x.ok()).ok_or_else(invalid_amount)?;
+            let y = parts.next().and_then(|y| y.ok()).ok_or_else(invalid_amount)?;
+            result.push(Instruction::Jump(Vector::new(x, y)));
+            continue;
+        }
 
+        let amount = match amount.parse::<i32>() {
+            Ok(amount) if amount >= 0 => amount,
+            _ => return Err(invalid_amount())
+        };
         result.push(
             match direction {
                 "R" => Instruction::Right(amount),
                 "L" => Instruction::Left(amount),
                 "U" => Instruction::Up(amount),
                 "D" => Instruction::Down(amount),
-                _ => panic!("invalid instruction {}", instruction)
+                "NE" => Instruction::UpRight(amount),
+                "NW" => Instruction::UpLeft(amount),
+                "SE" => Instruction::DownRight(amount),
+                "SW" => Instruction::DownLeft(amount),
+                _ => return Err(ParseError::UnknownDirection(start, instruction.to_string()))
             }
         )
     }
 
-    result
+    Ok(result)
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        parse("R2, NE3,J-4:10,SW0"),
+        Ok(vec![
+            Instruction::Right(2),
+            Instruction::UpRight(3),
+            Instruction::Jump(Vector::new(-4, 10)),
+            Instruction::DownLeft(0)
+        ])
+    );
+    assert_eq!(parse("R2,X3"), Err(ParseError::UnknownDirection(3, "X3".to_string())));
+    assert_eq!(parse("R2,U-1").unwrap_err().offset(), 3);
+    assert_eq!(parse("R2,J1"), Err(ParseError::InvalidAmount(3, "J1".to_string())));
+    assert_eq!(parse("R2,,L1"), Err(ParseError::Empty(3)));
+    assert_eq!(parse("R2,  3"), Err(ParseError::UnknownDirection(5, "3".to_string())));
 }
 
 #[derive(Debug)]
@@ -131,6 +247,11 @@ impl Point {
         self.cables.len() > 1
     }
 
+    // the cable passes the point more than once
+    pub fn is_self_crossing(&self, cable: CableId) -> bool {
+        self.cables.get(&cable).is_some_and(|distances| distances.len() > 1)
+    }
+
     pub fn min_distance(&self, cable: CableId) -> Option<usize> {
         self.cables.get(&cable).and_then(|x| x.iter().min()).map(|x| *x)
     }
@@ -144,7 +265,7 @@ impl Point {
 #[derive(Debug)]
 struct Board {
     central_port: Vector,
-    data: HashMap<Vector, Point>
+    data: Grid<Point>
 }
 
 
@@ -152,36 +273,53 @@ impl Board {
     pub fn new() -> Self {
         Board {
             central_port: Vector::new(0, 0),
-            data: HashMap::new()
+            data: Grid::new()
         }
     }
 
     pub fn add_cable(&mut self, cable_id: CableId, path: &Path) {
-        let mut last = self.central_port.clone();
+        let central_port = self.central_port;
+        self.add_cable_at(cable_id, central_port, path);
+    }
+
+    pub fn add_cable_at(&mut self, cable_id: CableId, start: Vector, path: &Path) {
+        let mut last = start;
         let mut distance = 1;
 
         for instruction in path {
+            if !instruction.is_drawn() {
+                last = instruction + &last;
+                continue;
+            }
+
             // coordinates contains the first element (starting point)
             // --> skip first to not mess up intersections and distance
             for v in instruction.coordinates(last).into_iter().skip(1) {
-                self.data.entry(v).or_insert_with(|| Point::new()).add(cable_id, distance);
+                self.data.get_or_insert_with((v.x, v.y), Point::new).add(cable_id, distance);
                 distance = distance + 1;
                 last = v;
             }
         }
     }
 
-    pub fn intersections(&self) -> impl Iterator<Item=(&Point, &Vector)> {
+    pub fn intersections(&self) -> impl Iterator<Item=(&Point, Vector)> {
         self.data.iter()
             .filter(|(_, v)| v.is_intersection())
-            .map(|(k, v)| (v, k))
+            .map(|(k, v)| (v, Vector::new(k.0, k.1)))
+    }
+
+    #[cfg(test)]
+    pub fn self_crossings(&self) -> impl Iterator<Item=(CableId, Vector)> + '_ {
+        self.data.iter().flat_map(|(k, v)| v.cables.keys()
+            .filter(move |cable| v.is_self_crossing(**cable))
+            .map(move |cable| (*cable, Vector::new(k.0, k.1))))
     }
 
     pub fn closest_intersection<F>(&self, intersection_fun: F) -> Option<usize>
         where F: Fn(&Point, &Vector) -> usize
     {
         self.intersections()
-            .map(|(point, v)| intersection_fun(point, v))
+            .map(|(point, v)| intersection_fun(point, &v))
             .min()
     }
 }
@@ -194,10 +332,55 @@ fn manhattan_distance(a: &Vector, b: &Vector) -> usize {
 
 fn main() {
     let input = fs::read_to_string("../input.txt").unwrap();
+    let paths: Vec<Path> = input.lines()
+        .enumerate()
+        .map(|(i, data)| parse(data).unwrap_or_else(|e| panic!("line {}: {}", i + 1, e)))
+        .collect();
+
+    // day03 [--segments] [--stats] [--svg FILE]
+    let args = Args::from_env();
+
+    let mut wires = Wires::new();
+    for (i, path) in paths.iter().enumerate() {
+        wires.add_cable(i as i32, path);
+    }
+
+    if args.flag("--stats") {
+        println!("Total length: {}", wires.total_length());
+        for cable in wires.cables() {
+            let bounds = wires.bounds(cable).unwrap();
+            println!(
+                "Wire {}: length {}, bounds {:?} to {:?}, {} self crossings",
+                cable, wires.length(cable), bounds.min, bounds.max,
+                wires.self_crossings().iter().filter(|(c, _, _)| *c == cable).count()
+            );
+        }
+        for run in wires.shared_runs() {
+            println!(
+                "Wires {} and {} share {} steps from {:?} to {:?}",
+                run.cables.0, run.cables.1, run.len(), run.from, run.to
+            );
+        }
+    }
+
+    if let Some(file) = args.option("--svg") {
+        fs::write(file, svg::render(&wires)).unwrap();
+    }
+
+    if args.flag("--segments") {
+        let mh = wires.closest_crossing(|c| manhattan_distance(&wires.central_port, &c.position))
+            .expect("at least one intersection");
+        println!("Closest intersection manhattan distance: {:?}", mh);
+
+        let closest = wires.closest_crossing(|c| c.total_steps())
+            .expect("at least one intersection");
+        println!("Closest intersection: {:?}", closest);
+        return;
+    }
 
     let mut board = Board::new();
-    for (i, data) in input.lines().enumerate() {
-        board.add_cable(i as i32, &parse(data.split(",")));
+    for (i, path) in paths.iter().enumerate() {
+        board.add_cable(i as i32, path);
     }
 
     let mh = board.closest_intersection(|_, v| manhattan_distance(&board.central_port, v))
@@ -214,8 +397,8 @@ fn main() {
 fn test_cable_intersection_1() {
     let mut board = Board::new();
 
-    let cable1 = parse("R2".split(","));
-    let cable2 = parse("U1,R1,D2".split(","));
+    let cable1 = parse("R2").unwrap();
+    let cable2 = parse("U1,R1,D2").unwrap();
     board.add_cable(1, &cable1);
     board.add_cable(2, &cable2);
 
@@ -236,8 +419,8 @@ fn test_cable_intersection_1() {
 fn test_cable_intersection_2() {
     let mut board = Board::new();
 
-    let cable1 = parse("R8,U5,L5,D3".split(","));
-    let cable2 = parse("U7,R6,D4,L4".split(","));
+    let cable1 = parse("R8,U5,L5,D3").unwrap();
+    let cable2 = parse("U7,R6,D4,L4").unwrap();
     board.add_cable(1, &cable1);
     board.add_cable(2, &cable2);
 
@@ -252,8 +435,8 @@ fn test_cable_intersection_2() {
 fn test_cable_intersection_3() {
     let mut board = Board::new();
 
-    let cable1 = parse("R75,D30,R83,U83,L12,D49,R71,U7,L72".split(","));
-    let cable2 = parse("U62,R66,U55,R34,D71,R55,D58,R83".split(","));
+    let cable1 = parse("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap();
+    let cable2 = parse("U62,R66,U55,R34,D71,R55,D58,R83").unwrap();
     board.add_cable(1, &cable1);
     board.add_cable(2, &cable2);
 
@@ -267,3 +450,111 @@ fn test_cable_intersection_3() {
     );
 }
 
+#[test]
+fn test_segments_match_board() {
+    let examples: Vec<Vec<&str>> = vec![
+        vec!["R2", "U1,R1,D2"],
+        vec!["R8,U5,L5,D3", "U7,R6,D4,L4"],
+        vec!["R75,D30,R83,U83,L12,D49,R71,U7,L72", "U62,R66,U55,R34,D71,R55,D58,R83"],
+        vec!["R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51", "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"],
+        // collinear overlaps, self crossings, a cable returning to the start and three cables
+        vec!["R10,L4,U3,D6", "R3,R5,U2,L20", "U1,R2,D1,L2,R7,U5,D1"],
+        vec!["U2,R2,D2,L4", "L1,R0,U4,R1,D4"],
+        // diagonals which cross between cells, diagonals on top of each other and jumps
+        vec!["NE3,SE3,J-10:0,R20", "U1,R5,SW4", "NW2,J3:3,SE5,NE1", "J1:0,NE5"],
+        vec!["J2:-3,U6,L4", "J0:1,NE4,NW1,SE9,L9"]
+    ];
+
+    for example in examples {
+        let mut board = Board::new();
+        let mut wires = Wires::new();
+        for (i, data) in example.iter().enumerate() {
+            // every wire starts somewhere else
+            let start = Vector::new(i as i32, -(i as i32));
+            board.add_cable_at(i as i32, start, &parse(data).unwrap());
+            wires.add_cable_at(i as i32, start, &parse(data).unwrap());
+        }
+
+        let mut expected: Vec<(Vector, usize)> = board.intersections()
+            .map(|(point, v)| (v, point.total_min_distance()))
+            .collect();
+        expected.sort_by_key(|(v, _)| (v.x, v.y));
+        let crossings: Vec<(Vector, usize)> = wires.crossings().iter()
+            .map(|crossing| (crossing.position, crossing.total_steps()))
+            .collect();
+
+        assert_eq!(crossings, expected, "{:?}", example);
+    }
+}
+
+#[test]
+fn test_segments_crossing_steps() {
+    let mut wires = Wires::new();
+    wires.add_cable(1, &parse("R8,U5,L5,D3").unwrap());
+    wires.add_cable(2, &parse("U7,R6,D4,L4").unwrap());
+    wires.add_cable(3, &parse("D1,R1000000,U4,L1000000").unwrap());
+
+    let crossings = wires.crossings();
+    let positions: Vec<Vector> = crossings.iter().map(|c| c.position).collect();
+    // the third cable runs along the last segment of the second one
+    let along: Vec<Vector> = (2..7).map(|x| Vector::new(x, 3)).collect();
+    assert_eq!(positions[1..6], along[..]);
+    assert_eq!(positions[0], Vector::new(0, 3));
+    assert_eq!(positions[6..], [Vector::new(6, 5), Vector::new(8, 3)]);
+
+    assert_eq!(crossings[0].steps, vec![(2, 3), (3, 2000005)].into_iter().collect());
+    assert_eq!(crossings[2].steps.len(), 3);
+    assert_eq!(crossings[2].total_steps(), 20 + 20 + 2000002);
+    assert_eq!(wires.closest_crossing(|c| c.total_steps()), Some(30));
+}
+
+#[test]
+fn test_segments_self_crossings() {
+    let examples: Vec<&str> = vec![
+        "R10,L4,U3,D6", "U1,R2,D1,L2,R7,U5,D1", "U2,R2,D2,L4,U4", "NE3,SE3,NW6,J-1:0,R2", "R8,U5,L5,D3"
+    ];
+
+    for example in examples {
+        let mut board = Board::new();
+        let mut wires = Wires::new();
+        board.add_cable(1, &parse(example).unwrap());
+        wires.add_cable(1, &parse(example).unwrap());
+
+        let mut expected: Vec<Vector> = board.self_crossings().map(|(_, v)| v).collect();
+        expected.sort_by_key(|v| (v.x, v.y));
+        let positions: Vec<Vector> = wires.self_crossings().iter().map(|(_, v, _)| *v).collect();
+        assert_eq!(positions, expected, "{}", example);
+    }
+
+    let mut wires = Wires::new();
+    wires.add_cable(1, &parse("U2,R2,D2,L4,U1,R3").unwrap());
+    assert_eq!(wires.self_crossings(), vec![(1, Vector::new(0, 1), vec![1, 13])]);
+}
+
+#[test]
+fn test_segments_stats() {
+    let mut wires = Wires::new();
+    wires.add_cable(1, &parse("R10,U3,J-2:0,D6").unwrap());
+    wires.add_cable(2, &parse("U1,R2,D1,R5,NE2").unwrap());
+
+    assert_eq!(wires.cables(), vec![1, 2]);
+    assert_eq!(wires.length(1), 19);
+    assert_eq!(wires.length(2), 11);
+    assert_eq!(wires.length(3), 0);
+    assert_eq!(wires.total_length(), 30);
+    assert_eq!(wires.bounds(1), Some(common::grid::Bounds::new((0, -3), (10, 3))));
+    assert_eq!(wires.bounds(2), Some(common::grid::Bounds::new((0, 0), (9, 2))));
+    assert_eq!(wires.bounds(3), None);
+
+    let runs = wires.shared_runs();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].cables, (1, 2));
+    assert_eq!((runs[0].from, runs[0].to, runs[0].len()), (Vector::new(2, 0), Vector::new(7, 0), 5));
+
+    let svg = svg::render(&wires);
+    assert!(svg.starts_with("<svg"));
+    // the jump splits the first cable, the first cable also crosses itself where it comes back down
+    assert_eq!(svg.matches("<polyline").count(), 3);
+    assert_eq!(wires.self_crossings(), vec![(1, Vector::new(8, 0), vec![8, 16])]);
+    assert_eq!(svg.matches("<circle").count(), wires.crossings().len() + 2);
+}
@@ -0,0 +1,378 @@
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use common::grid::Bounds;
+use {CableId, Path, Vector};
+
+
+// straight piece of a cable, `steps` is the length of the cable up to `from`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub cable: CableId,
+    pub from: Vector,
+    pub to: Vector,
+    pub steps: usize
+}
+
+impl Segment {
+    pub fn is_horizontal(&self) -> bool {
+        self.from.y == self.to.y
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.from.x == self.to.x
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        !self.is_horizontal() && !self.is_vertical()
+    }
+
+    // unit step along the segment
+    pub fn direction(&self) -> Vector {
+        Vector::new((self.to.x - self.from.x).signum(), (self.to.y - self.from.y).signum())
+    }
+
+    // diagonal steps count once, like on the board
+    pub fn len(&self) -> usize {
+        distance(self.from, self.to)
+    }
+
+    pub fn min(&self) -> Vector {
+        Vector::new(cmp::min(self.from.x, self.to.x), cmp::min(self.from.y, self.to.y))
+    }
+
+    pub fn max(&self) -> Vector {
+        Vector::new(cmp::max(self.from.x, self.to.x), cmp::max(self.from.y, self.to.y))
+    }
+
+    // steps of the cable when it reaches `position`, which has to be on the segment
+    pub fn steps_to(&self, position: Vector) -> usize {
+        self.steps + distance(self.from, position)
+    }
+
+    fn at(&self, step: i32) -> Vector {
+        let direction = self.direction();
+        Vector::new(self.from.x + direction.x * step, self.from.y + direction.y * step)
+    }
+
+    // every cell on both segments, works for any pair of directions
+    pub fn shared_cells(&self, other: &Segment) -> Vec<Vector> {
+        let (a, b) = (self.direction(), other.direction());
+        let offset = Vector::new(other.from.x - self.from.x, other.from.y - self.from.y);
+        let cross = |u: Vector, v: Vector| u.x * v.y - u.y * v.x;
+        let dot = |u: Vector, v: Vector| u.x * v.x + u.y * v.y;
+        let (len_a, len_b) = (self.len() as i32, other.len() as i32);
+
+        let det = cross(a, b);
+        if det != 0 {
+            // diagonals can cross between cells, then they share none
+            let (t, s) = (cross(offset, b), cross(offset, a));
+            if t % det != 0 || s % det != 0 {
+                return Vec::new();
+            }
+            let (t, s) = (t / det, s / det);
+            return if t >= 0 && t <= len_a && s >= 0 && s <= len_b { vec![self.at(t)] } else { Vec::new() };
+        }
+
+        if cross(offset, a) != 0 {
+            return Vec::new();
+        }
+        // parallel on the same line, the range of the other segment in steps along this one
+        let start = dot(offset, a) / dot(a, a);
+        let end = start + len_b * dot(a, b) / dot(a, a);
+        (cmp::max(0, cmp::min(start, end))..=cmp::min(len_a, cmp::max(start, end)))
+            .map(|t| self.at(t))
+            .collect()
+    }
+}
+
+
+// steps between two cells, moving diagonally when possible
+fn distance(a: Vector, b: Vector) -> usize {
+    cmp::max((a.x - b.x).abs(), (a.y - b.y).abs()) as usize
+}
+
+
+// a position at least two cables pass through, with the fewest steps every cable needs to get there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crossing {
+    pub position: Vector,
+    pub steps: BTreeMap<CableId, usize>
+}
+
+impl Crossing {
+    pub fn total_steps(&self) -> usize {
+        self.steps.values().sum()
+    }
+}
+
+
+// two cables running on top of each other from `from` to `to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+    pub cables: (CableId, CableId),
+    pub from: Vector,
+    pub to: Vector
+}
+
+impl Run {
+    pub fn len(&self) -> usize {
+        distance(self.from, self.to)
+    }
+}
+
+
+// cables kept as segments, crossings are found with a sweep line instead of visiting every cell
+#[derive(Debug)]
+pub struct Wires {
+    pub central_port: Vector,
+    segments: Vec<Segment>
+}
+
+impl Wires {
+    pub fn new() -> Self {
+        Wires {
+            central_port: Vector::new(0, 0),
+            segments: Vec::new()
+        }
+    }
+
+    pub fn add_cable(&mut self, cable_id: CableId, path: &Path) {
+        let central_port = self.central_port;
+        self.add_cable_at(cable_id, central_port, path);
+    }
+
+    pub fn add_cable_at(&mut self, cable_id: CableId, start: Vector, path: &Path) {
+        let mut from = start;
+        let mut steps = 0;
+
+        for instruction in path {
+            let segment = Segment { cable: cable_id, from, to: instruction + &from, steps };
+            from = segment.to;
+            if !instruction.is_drawn() {
+                continue;
+            }
+
+            steps += segment.len();
+            if segment.len() > 0 {
+                self.segments.push(segment);
+            }
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    // in the order they were added
+    pub fn cables(&self) -> Vec<CableId> {
+        let mut cables: Vec<CableId> = Vec::new();
+        for segment in &self.segments {
+            if !cables.contains(&segment.cable) {
+                cables.push(segment.cable);
+            }
+        }
+        cables
+    }
+
+    // drawn length, jumps don't count
+    pub fn length(&self, cable: CableId) -> usize {
+        self.segments.iter()
+            .filter(|segment| segment.cable == cable)
+            .map(|segment| segment.len())
+            .sum()
+    }
+
+    pub fn total_length(&self) -> usize {
+        self.segments.iter().map(|segment| segment.len()).sum()
+    }
+
+    // bounds of every drawn cell of a cable, None for an unknown cable
+    pub fn bounds(&self, cable: CableId) -> Option<Bounds> {
+        self.segments.iter()
+            .filter(|segment| segment.cable == cable)
+            .fold(None, |bounds: Option<Bounds>, segment| {
+                let (min, max) = (segment.min(), segment.max());
+                Some(match bounds {
+                    Some(bounds) => bounds.extend((min.x, min.y)).extend((max.x, max.y)),
+                    None => Bounds::new((min.x, min.y), (max.x, max.y))
+                })
+            })
+    }
+
+    // sorted by position, like on the board the start of a cable and jump targets do not count
+    pub fn crossings(&self) -> Vec<Crossing> {
+        let mut result: Vec<Crossing> = self.passes().into_iter()
+            .filter(|(_, steps)| steps.len() > 1)
+            .map(|(position, steps)| Crossing {
+                position,
+                steps: steps.into_iter().map(|(cable, steps)| (cable, steps[0])).collect()
+            })
+            .collect();
+        result.sort_by_key(|crossing| (crossing.position.x, crossing.position.y));
+        result
+    }
+
+    pub fn closest_crossing<F>(&self, crossing_fun: F) -> Option<usize>
+        where F: Fn(&Crossing) -> usize
+    {
+        self.crossings().iter().map(crossing_fun).min()
+    }
+
+    // positions a cable passes more than once, with the steps of every pass
+    pub fn self_crossings(&self) -> Vec<(CableId, Vector, Vec<usize>)> {
+        let mut result: Vec<(CableId, Vector, Vec<usize>)> = self.passes().into_iter()
+            .flat_map(|(position, cables)| cables.into_iter()
+                .filter(|(_, steps)| steps.len() > 1)
+                .map(move |(cable, steps)| (cable, position, steps)))
+            .collect();
+        result.sort_by_key(|(cable, position, _)| (*cable, position.x, position.y));
+        result
+    }
+
+    // stretches where two different cables run on top of each other for at least one step
+    pub fn shared_runs(&self) -> Vec<Run> {
+        let mut result = Vec::new();
+        for (a, b) in self.collinear() {
+            let cells = a.shared_cells(b);
+            if a.cable != b.cable && cells.len() > 1 {
+                result.push(Run {
+                    cables: (a.cable, b.cable),
+                    from: cells[0],
+                    to: cells[cells.len() - 1]
+                });
+            }
+        }
+        result
+    }
+
+    // every position passed by at least two segments, with the steps of each cable passing it
+    fn passes(&self) -> HashMap<Vector, BTreeMap<CableId, Vec<usize>>> {
+        let mut passes: HashMap<Vector, BTreeMap<CableId, BTreeSet<usize>>> = HashMap::new();
+        {
+            let mut add = |position: Vector, a: &Segment, b: &Segment| {
+                for segment in &[a, b] {
+                    // the previous segment ends there, unless the cable starts or jumped there
+                    if position != segment.from {
+                        let steps = segment.steps_to(position);
+                        passes.entry(position).or_default().entry(segment.cable).or_default().insert(steps);
+                    }
+                }
+            };
+
+            self.sweep(&mut add);
+            for (a, b) in self.collinear() {
+                for position in a.shared_cells(b) {
+                    add(position, a, b);
+                }
+            }
+            self.diagonals(&mut add);
+        }
+
+        passes.into_iter()
+            .map(|(position, cables)| {
+                let cables = cables.into_iter()
+                    .map(|(cable, steps)| (cable, steps.into_iter().collect()))
+                    .collect();
+                (position, cables)
+            })
+            .collect()
+    }
+
+    // horizontal against vertical segments, sweeping from left to right
+    fn sweep<F: FnMut(Vector, &Segment, &Segment)>(&self, add: &mut F) {
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        enum Event {
+            // at the same x horizontals are added first and removed last, to include their ends
+            Insert(usize),
+            Query(usize),
+            Remove(usize)
+        }
+
+        let mut events = Vec::with_capacity(self.segments.len() * 2);
+        for (index, segment) in self.segments.iter().enumerate() {
+            if segment.is_horizontal() {
+                events.push((segment.min().x, Event::Insert(index)));
+                events.push((segment.max().x, Event::Remove(index)));
+            } else if segment.is_vertical() {
+                events.push((segment.from.x, Event::Query(index)));
+            }
+        }
+        events.sort();
+
+        // horizontal segments crossing the sweep line, by y
+        let mut active: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+        for (x, event) in events {
+            match event {
+                Event::Insert(index) => active.entry(self.segments[index].from.y).or_default().push(index),
+                Event::Remove(index) => {
+                    let y = self.segments[index].from.y;
+                    let row = active.get_mut(&y).unwrap();
+                    row.retain(|other| *other != index);
+                    if row.is_empty() {
+                        active.remove(&y);
+                    }
+                },
+                Event::Query(index) => {
+                    let vertical = &self.segments[index];
+                    for (y, row) in active.range(vertical.min().y..=vertical.max().y) {
+                        for horizontal in row {
+                            add(Vector::new(x, *y), vertical, &self.segments[*horizontal]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // pairs of segments on the same line which share at least one position
+    fn collinear(&self) -> Vec<(&Segment, &Segment)> {
+        // orientation and the value which is the same for every position on the line
+        let line = |segment: &Segment| {
+            let direction = segment.direction();
+            match (direction.x * direction.y, direction.x) {
+                (0, 0) => (0, segment.from.x),
+                (0, _) => (1, segment.from.y),
+                (1, _) => (2, segment.from.y - segment.from.x),
+                _ => (3, segment.from.y + segment.from.x)
+            }
+        };
+
+        let mut lines: HashMap<(i32, i32), Vec<&Segment>> = HashMap::new();
+        for segment in &self.segments {
+            lines.entry(line(segment)).or_default().push(segment);
+        }
+
+        let mut result = Vec::new();
+        for (_, mut segments) in lines {
+            // x grows along every line but vertical ones
+            segments.sort_by_key(|segment| (segment.min().x, segment.min().y));
+
+            for (i, a) in segments.iter().enumerate() {
+                for b in &segments[i + 1..] {
+                    if (b.min().x, b.min().y) > (a.max().x, a.max().y) {
+                        break;
+                    }
+                    result.push((*a, *b));
+                }
+            }
+        }
+        result
+    }
+
+    // diagonals are rare, they are compared against every segment which crosses them
+    fn diagonals<F: FnMut(Vector, &Segment, &Segment)>(&self, add: &mut F) {
+        let parallel = |a: Vector, b: Vector| a.x * b.y == a.y * b.x;
+
+        for diagonal in self.segments.iter().filter(|segment| segment.is_diagonal()) {
+            for other in &self.segments {
+                if parallel(diagonal.direction(), other.direction()) {
+                    continue;
+                }
+                for position in diagonal.shared_cells(other) {
+                    add(position, diagonal, other);
+                }
+            }
+        }
+    }
+}
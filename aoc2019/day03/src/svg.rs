@@ -0,0 +1,69 @@
+use std::cmp;
+use std::fmt::Write;
+
+use segments::Wires;
+use Vector;
+
+
+static COLORS: [&str; 6] = ["#1f77b4", "#2ca02c", "#9467bd", "#8c564b", "#17becf", "#bcbd22"];
+const CROSSING: &str = "#d62728";
+const SELF_CROSSING: &str = "#ff7f0e";
+
+
+// every cable as a polyline, y growing upwards like on the board, crossings drawn as circles
+pub fn render(wires: &Wires) -> String {
+    let port = wires.central_port;
+    let bounds = wires.cables().iter()
+        .filter_map(|cable| wires.bounds(*cable))
+        .fold((port, port), |(min, max), bounds| (
+            Vector::new(cmp::min(min.x, bounds.min.0), cmp::min(min.y, bounds.min.1)),
+            Vector::new(cmp::max(max.x, bounds.max.0), cmp::max(max.y, bounds.max.1))
+        ));
+    let size = cmp::max(bounds.1.x - bounds.0.x, bounds.1.y - bounds.0.y) as f64;
+    let stroke = (size / 1000.0).max(0.1);
+    let margin = stroke * 10.0;
+
+    let mut svg = String::new();
+    // the svg y axis points down, flipping it keeps north up
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+        bounds.0.x as f64 - margin, -bounds.1.y as f64 - margin,
+        (bounds.1.x - bounds.0.x) as f64 + 2.0 * margin, (bounds.1.y - bounds.0.y) as f64 + 2.0 * margin
+    ).unwrap();
+    writeln!(svg, "<g transform=\"scale(1,-1)\" fill=\"none\" stroke-width=\"{}\">", stroke).unwrap();
+
+    for (index, cable) in wires.cables().iter().enumerate() {
+        // a new polyline starts after every jump
+        let mut lines: Vec<Vec<Vector>> = Vec::new();
+        for segment in wires.segments().iter().filter(|segment| segment.cable == *cable) {
+            match lines.last_mut() {
+                Some(ref mut line) if *line.last().unwrap() == segment.from => line.push(segment.to),
+                _ => lines.push(vec![segment.from, segment.to])
+            }
+        }
+
+        for line in lines {
+            let points: Vec<String> = line.iter().map(|v| format!("{},{}", v.x, v.y)).collect();
+            writeln!(
+                svg, "<polyline stroke=\"{}\" points=\"{}\"/>",
+                COLORS[index % COLORS.len()], points.join(" ")
+            ).unwrap();
+        }
+    }
+
+    let circle = |svg: &mut String, position: Vector, color: &str| writeln!(
+        svg, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"none\"/>",
+        position.x, position.y, stroke * 4.0, color
+    ).unwrap();
+    for (_, position, _) in wires.self_crossings() {
+        circle(&mut svg, position, SELF_CROSSING);
+    }
+    for crossing in wires.crossings() {
+        circle(&mut svg, crossing.position, CROSSING);
+    }
+    circle(&mut svg, port, "black");
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use {Password, Rule};
+
+
+// reads the digits of a password from left to right, the fewer states the faster the counting
+pub trait Automaton {
+    fn start(&self) -> u32;
+    // None rejects the password no matter which digits follow
+    fn next(&self, state: u32, digit: u8) -> Option<u32>;
+    fn accepts(&self, state: u32) -> bool;
+}
+
+
+// counts passwords in `0..limit` digit by digit, rules without an automaton are checked on every
+// password the automata let through
+pub struct Counter<'a> {
+    automata: Vec<&'a dyn Automaton>,
+    opaque: Vec<&'a dyn Rule>,
+    digits: Vec<u8>,
+    // passwords below the current position for every combination of states, once no longer tight
+    memo: HashMap<(usize, Vec<u32>), u64>
+}
+
+impl<'a> Counter<'a> {
+    pub fn new(rules: &'a [Box<dyn Rule>]) -> Self {
+        let mut counter = Counter {
+            automata: Vec::new(),
+            opaque: Vec::new(),
+            digits: Vec::new(),
+            memo: HashMap::new()
+        };
+        for rule in rules {
+            match rule.automaton() {
+                Some(automaton) => counter.automata.push(automaton),
+                None => counter.opaque.push(rule.as_ref())
+            }
+        }
+        counter
+    }
+
+    // without opaque rules no password is ever visited
+    pub fn is_exact(&self) -> bool {
+        self.opaque.is_empty()
+    }
+
+    pub fn count_below(&mut self, limit: Password) -> u64 {
+        if limit <= 0 {
+            return 0;
+        }
+
+        self.digits = limit.to_string().bytes().map(|c| c - b'0').collect();
+        // the memo depends on the amount of remaining digits only, which changes with the limit
+        self.memo.clear();
+
+        let start = self.automata.iter().map(|automaton| automaton.start()).collect();
+        self.walk(0, true, false, start, 0)
+    }
+
+    fn step(&self, states: &[u32], digit: u8) -> Option<Vec<u32>> {
+        self.automata.iter().zip(states)
+            .map(|(automaton, state)| automaton.next(*state, digit))
+            .collect()
+    }
+
+    // leading zeros are skipped until the first other digit, `tight` while the prefix equals the limit
+    fn walk(&mut self, position: usize, tight: bool, started: bool, states: Vec<u32>, password: Password) -> u64 {
+        if position == self.digits.len() {
+            // the limit itself is excluded
+            if tight {
+                return 0;
+            }
+            // zero is the only password written with a zero digit that isn't skipped
+            let states = if started { Some(states) } else { self.step(&states, 0) };
+            let accepted = states.is_some_and(|states| self.automata.iter()
+                .zip(&states)
+                .all(|(automaton, state)| automaton.accepts(*state)));
+            return (accepted && self.opaque.iter().all(|rule| rule.matches(password))) as u64;
+        }
+
+        let cached = !tight && started && self.is_exact();
+        if cached {
+            if let Some(count) = self.memo.get(&(position, states.clone())) {
+                return *count;
+            }
+        }
+
+        let max = if tight { self.digits[position] } else { 9 };
+        let mut count = 0;
+        for digit in 0..=max {
+            let next_tight = tight && digit == max;
+            let next_password = password * 10 + digit as Password;
+            if !started && digit == 0 {
+                count += self.walk(position + 1, next_tight, false, states.clone(), next_password);
+            } else if let Some(next) = self.step(&states, digit) {
+                count += self.walk(position + 1, next_tight, true, next, next_password);
+            }
+        }
+
+        if cached {
+            self.memo.insert((position, states), count);
+        }
+        count
+    }
+}
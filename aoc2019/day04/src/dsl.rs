@@ -0,0 +1,364 @@
+use std::cmp;
+use std::fmt;
+
+use count::Automaton;
+use {Password, Rule};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual
+}
+
+impl Compare {
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "=" | "==" => Some(Compare::Equal),
+            "!=" => Some(Compare::NotEqual),
+            "<" => Some(Compare::Less),
+            "<=" => Some(Compare::LessOrEqual),
+            ">" => Some(Compare::Greater),
+            ">=" => Some(Compare::GreaterOrEqual),
+            _ => None
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Compare::Equal => "==",
+            Compare::NotEqual => "!=",
+            Compare::Less => "<",
+            Compare::LessOrEqual => "<=",
+            Compare::Greater => ">",
+            Compare::GreaterOrEqual => ">="
+        }
+    }
+
+    pub fn test(&self, value: u32, other: u32) -> bool {
+        match self {
+            Compare::Equal => value == other,
+            Compare::NotEqual => value != other,
+            Compare::Less => value < other,
+            Compare::LessOrEqual => value <= other,
+            Compare::Greater => value > other,
+            Compare::GreaterOrEqual => value >= other
+        }
+    }
+}
+
+
+impl fmt::Display for Compare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+
+// a rule tree, the leaves look at the digits from left to right
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    NonDecreasing,
+    // the longest run of equal digits
+    RunLength(Compare, u32),
+    // any run of equal digits
+    ExistsRunLength(Compare, u32),
+    DigitCount(Compare, u32),
+    Contains(u8),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>)
+}
+
+// counts are capped one above the compared value, beyond it every comparison stays the same
+const MAX_COUNT: u32 = 20;
+
+fn cap(count: u32, other: u32) -> u32 {
+    cmp::min(count, cmp::min(other.saturating_add(1), MAX_COUNT))
+}
+
+impl Expr {
+    // the parts of a top level `and`, every one of them can be added as its own rule
+    pub fn split(self) -> Vec<Expr> {
+        match self {
+            Expr::And(a, b) => {
+                let mut result = a.split();
+                result.extend(b.split());
+                result
+            },
+            expr => vec![expr]
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        !matches!(self, Expr::And(..) | Expr::Or(..) | Expr::Not(..))
+    }
+}
+
+// the last digit is kept in the lowest 4 bits, 10 before the first digit, counts 8 bits each above it
+impl Automaton for Expr {
+    fn start(&self) -> u32 {
+        10
+    }
+
+    fn next(&self, state: u32, digit: u8) -> Option<u32> {
+        let (last, digit) = (state & 0xf, digit as u32);
+        let (run, other) = ((state >> 4) & 0xff, state >> 12);
+        let run = if last == digit { run + 1 } else { 1 };
+
+        let other = match self {
+            Expr::NonDecreasing if last != 10 && digit < last => return None,
+            Expr::NonDecreasing => 0,
+            Expr::RunLength(_, value) => cap(cmp::max(other, run), *value),
+            // a run is only complete once a different digit follows
+            Expr::ExistsRunLength(compare, value) if last != digit && last != 10 => {
+                let previous = (state >> 4) & 0xff;
+                (other == 1 || compare.test(previous, *value)) as u32
+            },
+            Expr::ExistsRunLength(..) => other,
+            Expr::DigitCount(_, value) => cap(other + 1, *value),
+            Expr::Contains(expected) => (other == 1 || digit == *expected as u32) as u32,
+            _ => panic!("only leaves have an automaton")
+        };
+        let run = match self {
+            Expr::RunLength(_, value) | Expr::ExistsRunLength(_, value) => cap(run, *value),
+            // the other leaves don't need it, which keeps the amount of states down
+            _ => 0
+        };
+
+        Some(other << 12 | run << 4 | digit)
+    }
+
+    fn accepts(&self, state: u32) -> bool {
+        let (run, other) = ((state >> 4) & 0xff, state >> 12);
+        match self {
+            Expr::NonDecreasing => true,
+            Expr::RunLength(compare, value) | Expr::DigitCount(compare, value) => compare.test(other, *value),
+            Expr::ExistsRunLength(compare, value) => other == 1 || compare.test(run, *value),
+            Expr::Contains(_) => other == 1,
+            _ => panic!("only leaves have an automaton")
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // parentheses only where the precedence needs them
+        let nested = |f: &mut fmt::Formatter, expr: &Expr, binds: fn(&Expr) -> bool| if binds(expr) {
+            write!(f, "({})", expr)
+        } else {
+            write!(f, "{}", expr)
+        };
+
+        match self {
+            Expr::NonDecreasing => write!(f, "non_decreasing"),
+            Expr::RunLength(compare, value) => write!(f, "run_length {} {}", compare, value),
+            Expr::ExistsRunLength(compare, value) => write!(f, "exists run_length {} {}", compare, value),
+            Expr::DigitCount(compare, value) => write!(f, "digit_count {} {}", compare, value),
+            Expr::Contains(digit) => write!(f, "contains {}", digit),
+            Expr::And(a, b) => {
+                nested(f, a, |expr| matches!(expr, Expr::Or(..)))?;
+                write!(f, " and ")?;
+                nested(f, b, |expr| matches!(expr, Expr::Or(..) | Expr::And(..)))
+            },
+            Expr::Or(a, b) => {
+                write!(f, "{} or ", a)?;
+                nested(f, b, |expr| matches!(expr, Expr::Or(..)))
+            },
+            Expr::Not(a) => {
+                write!(f, "not ")?;
+                nested(f, a, |expr| matches!(expr, Expr::Or(..) | Expr::And(..)))
+            }
+        }
+    }
+}
+
+impl Rule for Expr {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn matches(&self, password: Password) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(password) && b.matches(password),
+            Expr::Or(a, b) => a.matches(password) || b.matches(password),
+            Expr::Not(a) => !a.matches(password),
+            leaf => {
+                let mut state = Some(leaf.start());
+                for digit in password.to_string().bytes().filter(|c| c.is_ascii_digit()) {
+                    state = state.and_then(|state| leaf.next(state, digit - b'0'));
+                }
+                state.is_some_and(|state| leaf.accepts(state))
+            }
+        }
+    }
+
+    fn automaton(&self) -> Option<&dyn Automaton> {
+        if self.is_leaf() { Some(self) } else { None }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnexpectedEnd,
+    // offset of the token in the rule
+    UnexpectedToken(usize, String),
+    InvalidNumber(usize, String)
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of rule"),
+            ParseError::UnexpectedToken(offset, token) => write!(f, "unexpected '{}' at {}", token, offset),
+            ParseError::InvalidNumber(offset, token) => write!(f, "invalid number '{}' at {}", token, offset)
+        }
+    }
+}
+
+
+fn tokenize(input: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let same_kind = |other: char| match c {
+            'a'..='z' | '_' => other.is_ascii_lowercase() || other == '_',
+            '0'..='9' => other.is_ascii_digit(),
+            '<' | '>' | '=' | '!' => other == '=',
+            _ => false
+        };
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+        while let Some(&(index, next)) = chars.peek() {
+            if !same_kind(next) {
+                break;
+            }
+            end = index + next.len_utf8();
+            chars.next();
+        }
+        tokens.push((start, &input[start..end]));
+    }
+
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, &'a str)>,
+    position: usize
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).map(|(_, token)| *token)
+    }
+
+    fn next(&mut self) -> Result<(usize, &'a str), ParseError> {
+        let token = self.tokens.get(self.position).cloned().ok_or(ParseError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), ParseError> {
+        match self.next()? {
+            (_, token) if token == expected => Ok(()),
+            (offset, token) => Err(ParseError::UnexpectedToken(offset, token.to_string()))
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, ParseError> {
+        let (offset, token) = self.next()?;
+        token.parse().map_err(|_| ParseError::InvalidNumber(offset, token.to_string()))
+    }
+
+    fn comparison(&mut self) -> Result<(Compare, u32), ParseError> {
+        let (offset, token) = self.next()?;
+        let compare = Compare::parse(token)
+            .ok_or_else(|| ParseError::UnexpectedToken(offset, token.to_string()))?;
+        Ok((compare, self.number()?))
+    }
+
+    // `or` binds weaker than `and`, which binds weaker than `not`
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.peek() == Some("or") {
+            self.position += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.not()?;
+        while self.peek() == Some("and") {
+            self.position += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, ParseError> {
+        if self.peek() == Some("not") {
+            self.position += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        let (offset, token) = self.next()?;
+        match token {
+            "(" => {
+                let expr = self.or()?;
+                self.expect(")")?;
+                Ok(expr)
+            },
+            "non_decreasing" => Ok(Expr::NonDecreasing),
+            "run_length" => self.comparison().map(|(compare, value)| Expr::RunLength(compare, value)),
+            "exists" => {
+                self.expect("run_length")?;
+                self.comparison().map(|(compare, value)| Expr::ExistsRunLength(compare, value))
+            },
+            "digit_count" => self.comparison().map(|(compare, value)| Expr::DigitCount(compare, value)),
+            "contains" => {
+                let (offset, token) = self.next()?;
+                match token.parse() {
+                    Ok(digit) if digit < 10 => Ok(Expr::Contains(digit)),
+                    _ => Err(ParseError::InvalidNumber(offset, token.to_string()))
+                }
+            },
+            _ => Err(ParseError::UnexpectedToken(offset, token.to_string()))
+        }
+    }
+}
+
+// e.g. `non_decreasing and (exists run_length == 2 or not contains 7)`
+pub fn parse(input: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser { tokens: tokenize(input), position: 0 };
+    let expr = parser.or()?;
+    match parser.tokens.get(parser.position) {
+        Some((offset, token)) => Err(ParseError::UnexpectedToken(*offset, token.to_string())),
+        None => Ok(expr)
+    }
+}
+
+// one rule per line, all of them have to match, empty lines and lines starting with # are skipped
+pub fn parse_config(input: &str) -> Result<Vec<Expr>, (usize, ParseError)> {
+    let mut rules = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        rules.extend(parse(line).map_err(|e| (index + 1, e))?.split());
+    }
+    Ok(rules)
+}
@@ -1,8 +1,31 @@
+extern crate common;
+
+mod count;
+mod dsl;
+
+use std::fmt;
+use std::fs;
+use std::thread;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::args::Args;
+use count::{Automaton, Counter};
 
 type Password = i64;
 
-trait Rule {
+// rules are shared between the threads of `generate_parallel`
+trait Rule: Sync {
     fn matches(&self, password: Password) -> bool;
+
+    fn name(&self) -> String {
+        String::from("unnamed")
+    }
+
+    // rules which can be checked digit by digit are counted without visiting every password
+    fn automaton(&self) -> Option<&dyn Automaton> {
+        None
+    }
 }
 
 
@@ -10,6 +33,10 @@ struct AdjacentDigitsRule {
 }
 
 impl Rule for AdjacentDigitsRule {
+    fn name(&self) -> String {
+        String::from("adjacent digits")
+    }
+
     fn matches(&self, password: Password) -> bool {
         let mut last = None;
         for c in password.to_string().chars() {
@@ -21,6 +48,26 @@ impl Rule for AdjacentDigitsRule {
 
         false
     }
+
+    fn automaton(&self) -> Option<&dyn Automaton> {
+        Some(self)
+    }
+}
+
+// the last digit in the low bits, whether two equal digits were seen above them
+impl Automaton for AdjacentDigitsRule {
+    fn start(&self) -> u32 {
+        10
+    }
+
+    fn next(&self, state: u32, digit: u8) -> Option<u32> {
+        let found = state >= 16 || state == digit as u32;
+        Some(if found { 16 } else { 0 } + digit as u32)
+    }
+
+    fn accepts(&self, state: u32) -> bool {
+        state >= 16
+    }
 }
 
 impl AdjacentDigitsRule {
@@ -33,6 +80,10 @@ struct DoubleAdjacentDigitsRule {
 }
 
 impl Rule for DoubleAdjacentDigitsRule {
+    fn name(&self) -> String {
+        String::from("double adjacent digits")
+    }
+
     fn matches(&self, password: Password) -> bool {
         let mut last = None;
         let mut repetition = 0;
@@ -50,6 +101,31 @@ impl Rule for DoubleAdjacentDigitsRule {
 
         repetition == 1
     }
+
+    fn automaton(&self) -> Option<&dyn Automaton> {
+        Some(self)
+    }
+}
+
+// the last digit, how often it repeats so far (capped at 3) and whether a pair was seen
+impl Automaton for DoubleAdjacentDigitsRule {
+    fn start(&self) -> u32 {
+        10
+    }
+
+    fn next(&self, state: u32, digit: u8) -> Option<u32> {
+        let (last, run, found) = (state % 16, state / 16 % 4, state >= 64);
+        let (run, found) = if last == digit as u32 {
+            (std::cmp::min(run + 1, 3), found)
+        } else {
+            (1, found || run == 2)
+        };
+        Some(if found { 64 } else { 0 } + run * 16 + digit as u32)
+    }
+
+    fn accepts(&self, state: u32) -> bool {
+        state >= 64 || state / 16 % 4 == 2
+    }
 }
 
 impl DoubleAdjacentDigitsRule {
@@ -63,6 +139,10 @@ struct GrowingRule {
 }
 
 impl Rule for GrowingRule {
+    fn name(&self) -> String {
+        String::from("growing")
+    }
+
     fn matches(&self, password: Password) -> bool {
         let mut last = -1;
 
@@ -76,6 +156,25 @@ impl Rule for GrowingRule {
 
         true
     }
+
+    fn automaton(&self) -> Option<&dyn Automaton> {
+        Some(self)
+    }
+}
+
+// one more than the last digit, so the start is below every digit
+impl Automaton for GrowingRule {
+    fn start(&self) -> u32 {
+        0
+    }
+
+    fn next(&self, state: u32, digit: u8) -> Option<u32> {
+        if (digit as u32) + 1 < state { None } else { Some(digit as u32 + 1) }
+    }
+
+    fn accepts(&self, _state: u32) -> bool {
+        true
+    }
 }
 
 impl GrowingRule {
@@ -98,31 +197,355 @@ impl PasswordGen {
         self.rules.push(rule);
     }
 
-    pub fn generate(&self, from: Password, to: Password) -> Vec<Password> {
-        let mut passwords = Vec::new();
+    // passwords are only checked while the iterator is advanced
+    pub fn generate(&self, from: Password, to: Password) -> Passwords<'_> {
+        Passwords {
+            rules: &self.rules,
+            next: from,
+            to,
+            stats: Stats::new(&self.rules)
+        }
+    }
+
+    // splits the range into chunks which are checked by `threads` threads, the passwords stay in order,
+    // with a `limit` no new chunks are started once enough passwords were found
+    pub fn generate_parallel(&self, from: Password, to: Password, threads: usize, limit: Option<usize>)
+        -> (Vec<Password>, Stats)
+    {
+        let threads = std::cmp::max(threads, 1);
+        let size = std::cmp::max((to - from) / (threads as Password * 16), 1);
+        let chunks: Vec<(Password, Password)> = (from..to).step_by(size as usize)
+            .map(|start| (start, std::cmp::min(start + size, to)))
+            .collect();
+
+        // chunks are taken in order, so the finished ones always start at the beginning of the range
+        let next_chunk = AtomicUsize::new(0);
+        let found = AtomicUsize::new(0);
+        let results = Mutex::new(vec![Vec::new(); chunks.len()]);
+        let stats = Mutex::new(Stats::new(&self.rules));
 
-        for password in from..to {
-            let matches = self.rules.iter().all(|rule| rule.matches(password));
-            if matches {
-                passwords.push(password);
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    if limit.is_some_and(|limit| found.load(Ordering::SeqCst) >= limit) {
+                        break;
+                    }
+                    let index = next_chunk.fetch_add(1, Ordering::SeqCst);
+                    let (start, end) = match chunks.get(index) {
+                        Some(chunk) => *chunk,
+                        None => break
+                    };
+
+                    let mut passwords = self.generate(start, end);
+                    let chunk: Vec<Password> = passwords.by_ref().collect();
+                    stats.lock().unwrap().merge(passwords.stats());
+                    found.fetch_add(chunk.len(), Ordering::SeqCst);
+                    results.lock().unwrap()[index] = chunk;
+                });
             }
+        });
+
+        let mut passwords: Vec<Password> = results.into_inner().unwrap().into_iter().flatten().collect();
+        if let Some(limit) = limit {
+            passwords.truncate(limit);
+        }
+        (passwords, stats.into_inner().unwrap())
+    }
+
+    // same passwords as `generate`, without keeping them around, negative passwords are never counted
+    pub fn count(&self, from: Password, to: Password) -> u64 {
+        if from >= to {
+            return 0;
         }
 
-        passwords
+        let mut counter = Counter::new(&self.rules);
+        counter.count_below(to) - counter.count_below(from)
+    }
+}
+
+
+// how many candidates every rule rejected, a candidate only counts for the first rule rejecting it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Stats {
+    checked: u64,
+    accepted: u64,
+    rejected: Vec<(String, u64)>
+}
+
+impl Stats {
+    fn new(rules: &[Box<dyn Rule>]) -> Self {
+        Stats {
+            checked: 0,
+            accepted: 0,
+            rejected: rules.iter().map(|rule| (rule.name(), 0)).collect()
+        }
+    }
+
+    fn merge(&mut self, other: &Stats) {
+        self.checked += other.checked;
+        self.accepted += other.accepted;
+        for (rejected, other) in self.rejected.iter_mut().zip(&other.rejected) {
+            rejected.1 += other.1;
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "checked {}, accepted {}", self.checked, self.accepted)?;
+        for (name, rejected) in &self.rejected {
+            let share = if self.checked == 0 { 0.0 } else { *rejected as f64 * 100.0 / self.checked as f64 };
+            writeln!(f, "  {}: rejected {} ({:.1}%)", name, rejected, share)?;
+        }
+        Ok(())
+    }
+}
+
+
+struct Passwords<'a> {
+    rules: &'a [Box<dyn Rule>],
+    next: Password,
+    to: Password,
+    stats: Stats
+}
+
+impl<'a> Passwords<'a> {
+    // covers the candidates checked so far
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+}
+
+impl<'a> Iterator for Passwords<'a> {
+    type Item = Password;
+
+    fn next(&mut self) -> Option<Password> {
+        while self.next < self.to {
+            let password = self.next;
+            self.next += 1;
+            self.stats.checked += 1;
+
+            match self.rules.iter().position(|rule| !rule.matches(password)) {
+                Some(index) => self.stats.rejected[index].1 += 1,
+                None => {
+                    self.stats.accepted += 1;
+                    return Some(password);
+                }
+            }
+        }
+        None
     }
 }
 
 
 fn main() {
+    // day04 [--enumerate] [--stats] [--threads N] [--first N] [--rule RULE | --rules FILE]
+    let args = Args::from_env();
+
+    let (from, to) = (152085, 670283);
+    let enumerate = args.flag("--enumerate");
+    let stats = args.flag("--stats");
+    let threads = args.option("--threads").map(|x| x.parse::<usize>().expect("invalid amount of threads"));
+    let first = args.option("--first").map(|x| x.parse::<usize>().expect("invalid amount of passwords"));
+
+    let count = |gen: &PasswordGen| {
+        if !enumerate && !stats && threads.is_none() && first.is_none() {
+            return gen.count(from, to);
+        }
+
+        let (passwords, result) = match threads {
+            Some(threads) => gen.generate_parallel(from, to, threads, first),
+            None => {
+                let mut passwords = gen.generate(from, to);
+                let found: Vec<Password> = passwords.by_ref().take(first.unwrap_or(usize::MAX)).collect();
+                (found, passwords.stats().clone())
+            }
+        };
+        if first.is_some() {
+            println!("{:?}", passwords);
+        }
+        if stats {
+            print!("{}", result);
+        }
+        passwords.len() as u64
+    };
+
+    let rules = match (args.option("--rule"), args.option("--rules")) {
+        (Some(rule), _) => Some(dsl::parse(rule).unwrap_or_else(|e| panic!("{}", e)).split()),
+        (None, Some(file)) => Some(dsl::parse_config(&fs::read_to_string(file).unwrap())
+            .unwrap_or_else(|(line, e)| panic!("line {}: {}", line, e))),
+        (None, None) => None
+    };
+    if let Some(rules) = rules {
+        let mut gen = PasswordGen::new();
+        for rule in rules {
+            gen.add_rule(Box::new(rule));
+        }
+        println!("found {} passwords", count(&gen));
+        return;
+    }
+
+    let mut gen = PasswordGen::new();
+    gen.add_rule(Box::new(GrowingRule::new()));
+    gen.add_rule(Box::new(AdjacentDigitsRule::new()));
+
+    println!("found {} passwords", count(&gen));
+
+    gen.add_rule(Box::new(DoubleAdjacentDigitsRule::new()));
+    println!("found {} passwords with double adjacent rule", count(&gen));
+}
+
+
+
+#[cfg(test)]
+struct EvenRule {
+}
+
+#[cfg(test)]
+impl Rule for EvenRule {
+    fn matches(&self, password: Password) -> bool {
+        password % 2 == 0
+    }
+}
+
+#[test]
+fn test_count_matches_generate() {
+    let ranges = [(0, 1000), (152085, 170283), (111110, 111123), (5, 6), (-20, 30), (99, 99)];
+
     let mut gen = PasswordGen::new();
     gen.add_rule(Box::new(GrowingRule::new()));
+    for &(from, to) in &ranges {
+        assert_eq!(gen.count(from, to), gen.generate(from.max(0), to).count() as u64);
+    }
+
+    gen.add_rule(Box::new(AdjacentDigitsRule::new()));
+    gen.add_rule(Box::new(DoubleAdjacentDigitsRule::new()));
+    for &(from, to) in &ranges {
+        assert_eq!(gen.count(from, to), gen.generate(from.max(0), to).count() as u64);
+    }
+
+    // the opaque rule is only checked on passwords the other rules accept
+    gen.add_rule(Box::new(EvenRule {}));
+    for &(from, to) in &ranges {
+        assert_eq!(gen.count(from, to), gen.generate(from.max(0), to).count() as u64);
+    }
+}
+
+#[test]
+fn test_count_large_ranges() {
+    let mut gen = PasswordGen::new();
+    gen.add_rule(Box::new(GrowingRule::new()));
+    // non decreasing digits never contain a zero, so every length k has C(k + 8, 8) of them, plus zero
+    assert_eq!(gen.count(0, 1_000_000_000_000), 293930);
+
     gen.add_rule(Box::new(AdjacentDigitsRule::new()));
+    // twelve non decreasing digits out of nine always repeat one
+    assert_eq!(gen.count(100_000_000_000, 1_000_000_000_000), 125970);
+}
+
+#[test]
+fn test_dsl_parse() {
+    use dsl::{parse, parse_config, Compare, Expr, ParseError};
 
-    let passwords = gen.generate(152085, 670283);
-    println!("found {} passwords", passwords.len());
+    assert_eq!(parse("non_decreasing"), Ok(Expr::NonDecreasing));
+    assert_eq!(parse("exists run_length==2"), Ok(Expr::ExistsRunLength(Compare::Equal, 2)));
+    assert_eq!(
+        parse("not contains 7 or digit_count = 6 and run_length >= 2"),
+        Ok(Expr::Or(
+            Box::new(Expr::Not(Box::new(Expr::Contains(7)))),
+            Box::new(Expr::And(
+                Box::new(Expr::DigitCount(Compare::Equal, 6)),
+                Box::new(Expr::RunLength(Compare::GreaterOrEqual, 2))
+            ))
+        ))
+    );
+    assert_eq!(parse("(contains 1 or contains 2) and contains 3").unwrap().split().len(), 2);
+
+    for text in &["not (contains 1 or contains 2) and contains 3", "run_length < 3 or not non_decreasing",
+                  "contains 1 and (contains 2 and exists run_length != 4)"] {
+        assert_eq!(parse(text).unwrap().to_string(), *text);
+    }
+
+    assert_eq!(parse("non_decreasing and"), Err(ParseError::UnexpectedEnd));
+    assert_eq!(parse("run_length 2"), Err(ParseError::UnexpectedToken(11, "2".to_string())));
+    assert_eq!(parse("run_length => 2"), Err(ParseError::InvalidNumber(12, ">".to_string())));
+    assert_eq!(parse("contains 12"), Err(ParseError::InvalidNumber(9, "12".to_string())));
+    assert_eq!(parse("(contains 1"), Err(ParseError::UnexpectedEnd));
+    assert_eq!(parse("contains 1)"), Err(ParseError::UnexpectedToken(10, ")".to_string())));
+
+    let config = "# part two\nnon_decreasing\n\nexists run_length == 2 and digit_count = 6\n";
+    assert_eq!(parse_config(config).map(|rules| rules.len()), Ok(3));
+    assert_eq!(parse_config("contains 1\nrun_length"), Err((2, ParseError::UnexpectedEnd)));
+}
+
+#[test]
+fn test_dsl_matches_rules() {
+    let rules = |text: &str| {
+        let mut gen = PasswordGen::new();
+        for rule in dsl::parse(text).unwrap().split() {
+            gen.add_rule(Box::new(rule));
+        }
+        gen
+    };
+
+    let mut gen = PasswordGen::new();
+    gen.add_rule(Box::new(GrowingRule::new()));
+    gen.add_rule(Box::new(AdjacentDigitsRule::new()));
+    let expected: Vec<Password> = gen.generate(100000, 300000).collect();
+    assert_eq!(rules("non_decreasing and run_length >= 2").generate(100000, 300000).collect::<Vec<_>>(), expected);
+    assert_eq!(rules("non_decreasing and run_length >= 2").count(100000, 300000), expected.len() as u64);
 
     gen.add_rule(Box::new(DoubleAdjacentDigitsRule::new()));
-    let passwords = gen.generate(152085, 670283);
-    println!("found {} passwords with double adjacent rule", passwords.len());
+    let expected: Vec<Password> = gen.generate(100000, 300000).collect();
+    assert_eq!(rules("non_decreasing and exists run_length == 2").generate(100000, 300000).collect::<Vec<_>>(), expected);
+    assert_eq!(rules("non_decreasing and exists run_length = 2").count(100000, 300000), expected.len() as u64);
+
+    // nested rules are counted by enumerating the passwords the leaves let through
+    let gen = rules("digit_count >= 2 and not (contains 3 or run_length > 1) and (contains 9 or exists run_length < 1)");
+    let expected = (0..2000).filter(|x| {
+        let digits = x.to_string();
+        digits.len() >= 2 && !digits.contains('3') && digits.contains('9')
+            && digits.as_bytes().windows(2).all(|pair| pair[0] != pair[1])
+    }).count();
+    assert_eq!(gen.count(0, 2000), expected as u64);
+    assert_eq!(gen.generate(0, 2000).count(), expected);
 }
 
+#[test]
+fn test_generate_stats() {
+    let mut gen = PasswordGen::new();
+    gen.add_rule(Box::new(EvenRule {}));
+    gen.add_rule(Box::new(GrowingRule::new()));
+    gen.add_rule(Box::new(dsl::parse("contains 4 or contains 6").unwrap()));
+
+    let mut passwords = gen.generate(10, 50);
+    assert_eq!(passwords.next(), Some(14));
+    // nothing after the first password was checked yet
+    assert_eq!(passwords.stats().checked, 5);
+    assert_eq!(passwords.by_ref().take(2).collect::<Vec<_>>(), vec![16, 24]);
+    assert_eq!(passwords.count(), 6);
+
+    let mut passwords = gen.generate(10, 50);
+    assert_eq!(passwords.by_ref().count(), 9);
+    let stats = passwords.stats().clone();
+    assert_eq!((stats.checked, stats.accepted), (40, 9));
+    assert_eq!(stats.rejected, vec![
+        ("unnamed".to_string(), 20),
+        ("growing".to_string(), 6),
+        ("contains 4 or contains 6".to_string(), 5)
+    ]);
+
+    for threads in 1..5 {
+        let (passwords, parallel) = gen.generate_parallel(10, 50, threads, None);
+        assert_eq!(passwords, gen.generate(10, 50).collect::<Vec<_>>());
+        assert_eq!(parallel, stats);
+
+        // the found passwords are the first ones, more than those may have been checked
+        let (passwords, parallel) = gen.generate_parallel(10, 50, threads, Some(3));
+        assert_eq!(passwords, vec![14, 16, 24]);
+        assert!(parallel.checked >= 15);
+    }
+
+    let (passwords, _) = gen.generate_parallel(50, 10, 4, None);
+    assert!(passwords.is_empty());
+}
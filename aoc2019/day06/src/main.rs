@@ -1,115 +1,154 @@
-use std::cell::RefCell;
-use std::rc::{Rc, Weak};
-use std::collections::HashMap;
+extern crate common;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs;
 
+mod tree;
+
+use common::args::Args;
+use tree::{NodeId, Tree};
+
 
 type PlanetId = String;
 
 
-struct Planet {
-    id: PlanetId,
-    orbits: Option<Rc<RefCell<Planet>>>,
-    orbited_by: Vec<Weak<RefCell<Planet>>>
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AtlasError {
+    // line number and the line
+    Malformed(usize, String),
+    SelfOrbit(usize, PlanetId),
+    // the body, the one it already orbits and the line that orbit was given on
+    DuplicateParent(usize, PlanetId, PlanetId, usize),
+    // the bodies along the cycle, starting with the one whose orbit closes it
+    Cycle(usize, Vec<PlanetId>),
+    UnknownPlanet(PlanetId)
 }
 
-impl fmt::Debug for Planet {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let parent = self.orbits.clone().map(|x| x.borrow().id.clone());
-        write!(
-            f,
-            "Planet {{ id: {}, orbits: {:?}, orbited_by: {:?} }}",
-            self.id, parent, self.orbited_by
-        )
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AtlasError::Malformed(line, text) => write!(f, "line {}: malformed orbit '{}'", line, text),
+            AtlasError::SelfOrbit(line, id) => write!(f, "line {}: {} orbits itself", line, id),
+            AtlasError::DuplicateParent(line, id, parent, first) => write!(
+                f, "line {}: {} already orbits {} since line {}", line, id, parent, first
+            ),
+            AtlasError::Cycle(line, ids) => write!(f, "line {}: orbits form a cycle {}", line, ids.join(" -> ")),
+            AtlasError::UnknownPlanet(id) => write!(f, "unknown planet {}", id)
+        }
     }
 }
 
-impl Planet {
-    fn new(id: PlanetId) -> Self {
-        Planet { id: id, orbits: None, orbited_by: Vec::new() }
-    }
 
-    fn orbits(&mut self, parent: Rc<RefCell<Planet>>) {
-        self.orbits = Some(parent);
-    }
+#[derive(Debug)]
+struct Atlas {
+    ids: HashMap<PlanetId, NodeId>,
+    planets: Tree<PlanetId>
+}
 
-    fn orbited_by(&mut self, other: Weak<RefCell<Planet>>) {
-        self.orbited_by.push(other);
+impl Atlas {
+    fn new() -> Self {
+        Atlas { ids: HashMap::new(), planets: Tree::new() }
     }
 
-    fn is_root(&self) -> bool {
-        self.orbits.is_none()
+    fn planet(&mut self, id: &PlanetId) -> NodeId {
+        let planets = &mut self.planets;
+        *self.ids.entry(id.clone()).or_insert_with(|| planets.add(id.clone()))
     }
 
-    fn depth(&self) -> usize {
-        match self.orbits {
-            Some(ref parent) => 1 + parent.borrow().depth(),
-            None => 0
-        }
-    }
+    // one `PARENT)BODY` per line, empty lines are skipped
+    fn parse(input: &str) -> Result<Self, AtlasError> {
+        let mut atlas = Atlas::new();
+        // line every body got its parent on
+        let mut lines: HashMap<NodeId, usize> = HashMap::new();
 
-    fn indirect_orbits(&self) -> usize {
-        match self.depth() {
-            0 => 0,
-            1 => 0,
-            x => x - 1
-        }
-    }
+        for (index, text) in input.lines().enumerate() {
+            let line = index + 1;
+            if text.trim().is_empty() {
+                continue;
+            }
 
-    fn all_orbits(&self) -> Vec<PlanetId> {
-        if self.orbits.is_none() {
-            return Vec::new();
+            let parts: Vec<&str> = text.trim().split(')').collect();
+            let valid = |id: &str| !id.is_empty() && id.chars().all(|c| c.is_alphanumeric());
+            if parts.len() != 2 || !parts.iter().all(|id| valid(id)) {
+                return Err(AtlasError::Malformed(line, text.to_string()));
+            }
+            let (parent, body) = (parts[0].to_string(), parts[1].to_string());
+            if parent == body {
+                return Err(AtlasError::SelfOrbit(line, body));
+            }
+
+            let (parent, body) = (atlas.planet(&parent), atlas.planet(&body));
+            if let Some(previous) = atlas.planets.parent(body) {
+                return Err(AtlasError::DuplicateParent(
+                    line, atlas.planets.get(body).clone(), atlas.planets.get(previous).clone(), lines[&body]
+                ));
+            }
+
+            atlas.planets.set_parent(body, parent);
+            lines.insert(body, line);
         }
 
-        let mut result = Vec::new();
-        let a = self.orbits.clone().unwrap();
-        result.push(a.borrow().id.clone());
-        result.extend(a.borrow().all_orbits());
-        result
+        // checked once all orbits are known, walking up on every line is quadratic for long chains
+        if !atlas.planets.unreachable().is_empty() {
+            return Err(atlas.first_cycle(&lines));
+        }
+        Ok(atlas)
     }
-}
 
+    // the cycle closed first by the lines, among the bodies no root leads to
+    fn first_cycle(&self, lines: &HashMap<NodeId, usize>) -> AtlasError {
+        let mut visited = HashSet::new();
+        let mut first: Option<(usize, NodeId)> = None;
+
+        // every body has a parent, walking up ends on a cycle
+        for start in self.planets.unreachable() {
+            let mut walk = Vec::new();
+            let mut id = *start;
+            while visited.insert(id) {
+                walk.push(id);
+                id = self.planets.parent(id).unwrap();
+            }
 
-#[derive(Debug)]
-struct Atlas {
-    planets: HashMap<PlanetId, Rc<RefCell<Planet>>>
-}
+            // otherwise the walk ran into a cycle found before
+            if let Some(position) = walk.iter().position(|other| *other == id) {
+                // a cycle is closed by the last of its lines
+                let body = *walk[position..].iter().max_by_key(|body| lines[body]).unwrap();
+                if first.map_or(true, |(line, _)| lines[&body] < line) {
+                    first = Some((lines[&body], body));
+                }
+            }
+        }
 
-impl Atlas {
-    fn new() -> Self {
-        Atlas { planets: HashMap::new() }
+        let (line, body) = first.expect("unreachable bodies without a cycle");
+        let mut cycle = vec![body];
+        let mut id = self.planets.parent(body).unwrap();
+        while id != body {
+            cycle.push(id);
+            id = self.planets.parent(id).unwrap();
+        }
+        AtlasError::Cycle(line, cycle.iter().map(|id| self.planets.get(*id).clone()).collect())
     }
 
-    fn create_orbiting_planet(&mut self, id: &PlanetId, orbiting: &PlanetId) {
-        let planet = self.planets.entry(id.clone())
-            .or_insert_with(|| Rc::new(RefCell::new(Planet::new(id.clone()))))
-            .clone();
-
-        let orbiting = self.planets.entry(orbiting.clone())
-            .or_insert_with(|| Rc::new(RefCell::new(Planet::new(orbiting.clone()))))
-            .clone();
-
-        orbiting.borrow_mut().orbited_by(Rc::downgrade(&planet));
-        planet.borrow_mut().orbits(orbiting);
+    fn id(&self, id: &PlanetId) -> Result<NodeId, AtlasError> {
+        self.ids.get(id).cloned().ok_or_else(|| AtlasError::UnknownPlanet(id.clone()))
     }
 
     fn roots(&self) -> Vec<PlanetId> {
-        self.planets.values()
-            .filter(|planet| planet.borrow().is_root())
-            .map(|planet| planet.borrow().id.clone())
+        self.planets.roots()
+            .map(|id| self.planets.get(id).clone())
             .collect()
     }
 
     fn direct_orbits(&self) -> usize {
-        self.planets.values()
-            .filter(|planet| !planet.borrow().is_root())
+        self.planets.ids()
+            .filter(|id| self.planets.parent(*id).is_some())
             .count()
     }
 
+    // every body orbits all of its ancestors, only the one of them it orbits directly doesn't count
     fn indirect_orbits(&self) -> usize {
-        self.planets.values()
-            .map(|planet| planet.borrow().indirect_orbits())
+        self.planets.ids()
+            .map(|id| self.planets.depth(id).saturating_sub(1))
             .sum()
     }
 
@@ -121,31 +160,125 @@ impl Atlas {
         self.planets.len()
     }
 
-    fn orbital_transfer(&self, from: &PlanetId, to: &PlanetId) -> Option<usize> {
-        let from_orbits = self.planets.get(from).unwrap().borrow().all_orbits();
-        let to_orbits = self.planets.get(to).unwrap().borrow().all_orbits();
+    // transfers between the bodies `from` and `to` are orbiting, None if either of them orbits nothing
+    // or they are part of different systems
+    fn orbital_transfer(&self, from: &PlanetId, to: &PlanetId) -> Result<Option<usize>, AtlasError> {
+        let (from, to) = (self.id(from)?, self.id(to)?);
+        Ok(match (self.planets.parent(from), self.planets.parent(to)) {
+            (Some(from), Some(to)) => self.planets.distance(from, to),
+            _ => None
+        })
+    }
+
+    fn names(&self, ids: Vec<NodeId>) -> Vec<PlanetId> {
+        ids.into_iter().map(|id| self.planets.get(id).clone()).collect()
+    }
+
+    // the bodies visited on the transfer, starting with the one `from` is orbiting
+    fn transfer_path(&self, from: &PlanetId, to: &PlanetId) -> Result<Option<Vec<PlanetId>>, AtlasError> {
+        let (from, to) = (self.id(from)?, self.id(to)?);
+        Ok(match (self.planets.parent(from), self.planets.parent(to)) {
+            (Some(from), Some(to)) => self.planets.path(from, to).map(|path| self.names(path)),
+            _ => None
+        })
+    }
 
-        for (index, planet) in from_orbits.iter().enumerate() {
-            if let Some(position) = to_orbits.iter().position(|x| x == planet) {
-                return Some(index + position)
+    // the body itself and everything orbiting it directly or indirectly
+    fn subtree_size(&self, id: &PlanetId) -> Result<usize, AtlasError> {
+        Ok(self.planets.subtree_size(self.id(id)?))
+    }
+
+    fn deepest(&self) -> Option<(PlanetId, usize)> {
+        self.planets.deepest().map(|id| (self.planets.get(id).clone(), self.planets.depth(id)))
+    }
+
+    // bodies orbiting the same parent, sorted by name
+    fn siblings(&self, id: &PlanetId) -> Result<Vec<PlanetId>, AtlasError> {
+        let mut siblings = self.names(self.planets.siblings(self.id(id)?));
+        siblings.sort();
+        Ok(siblings)
+    }
+
+    // None if the bodies are part of different systems
+    fn common_ancestor(&self, ids: &[PlanetId]) -> Result<Option<PlanetId>, AtlasError> {
+        let ids = ids.iter().map(|id| self.id(id)).collect::<Result<Vec<NodeId>, AtlasError>>()?;
+        Ok(self.planets.common_ancestor_of(&ids).map(|id| self.planets.get(id).clone()))
+    }
+
+    // every body in front of the ones orbiting it, siblings sorted by name
+    fn sorted(&self) -> Vec<NodeId> {
+        let by_name = |ids: &mut Vec<NodeId>| ids.sort_by(|a, b| self.planets.get(*b).cmp(self.planets.get(*a)));
+
+        let mut result = Vec::with_capacity(self.planets.len());
+        let mut stack: Vec<NodeId> = self.planets.roots().collect();
+        by_name(&mut stack);
+        while let Some(id) = stack.pop() {
+            result.push(id);
+            let mut children = self.planets.children(id).to_vec();
+            by_name(&mut children);
+            stack.extend(children);
+        }
+        result
+    }
+
+    // Graphviz with an edge from every body to the ones orbiting it
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph orbits {\n");
+        for id in self.sorted() {
+            let name = self.planets.get(id);
+            match self.planets.parent(id) {
+                Some(parent) => dot.push_str(&format!("    \"{}\" -> \"{}\";\n", self.planets.get(parent), name)),
+                None => dot.push_str(&format!("    \"{}\";\n", name))
             }
         }
+        dot.push_str("}\n");
+        dot
+    }
 
-        None
+    // one body per line, indented by two spaces per level
+    fn outline(&self) -> String {
+        self.sorted().into_iter()
+            .map(|id| format!("{}{}\n", "  ".repeat(self.planets.depth(id)), self.planets.get(id)))
+            .collect()
     }
 }
 
 
 fn main() {
-    let mut atlas = Atlas::new();
+    // day06 [--outline] [--dot FILE] [--path FROM,TO] [--common ID,ID,...] [--body ID]
+    let args = Args::from_env();
+    let ids = |value: &str| value.split(',').map(|id| id.to_string()).collect::<Vec<PlanetId>>();
 
     let input = fs::read_to_string("../input.txt").unwrap();
+    let atlas = Atlas::parse(&input).unwrap_or_else(|e| panic!("{}", e));
 
-    for line in input.lines() {
-        let mut s = line.split(")");
-        let parent = s.next().unwrap();
-        let planet = s.next().unwrap();
-        atlas.create_orbiting_planet(&planet.to_string(), &parent.to_string());
+    if args.flag("--outline") {
+        print!("{}", atlas.outline());
+        return;
+    }
+    if let Some(file) = args.option("--dot") {
+        fs::write(file, atlas.to_dot()).unwrap();
+    }
+    if let Some(value) = args.option("--path") {
+        let ids = ids(value);
+        assert_eq!(ids.len(), 2, "--path needs FROM,TO");
+        match atlas.transfer_path(&ids[0], &ids[1]) {
+            Ok(Some(path)) => println!("transfer path: {}", path.join(" -> ")),
+            Ok(None) => println!("no transfer between {} and {}", ids[0], ids[1]),
+            Err(e) => println!("{}", e)
+        }
+    }
+    if let Some(value) = args.option("--common") {
+        match atlas.common_ancestor(&ids(value)) {
+            Ok(ancestor) => println!("common ancestor: {:?}", ancestor),
+            Err(e) => println!("{}", e)
+        }
+    }
+    if let Some(id) = args.option("--body").map(|id| id.to_string()) {
+        match (atlas.subtree_size(&id), atlas.siblings(&id)) {
+            (Ok(size), Ok(siblings)) => println!("{}: {} bodies in its subtree, siblings {:?}", id, size, siblings),
+            (Err(e), _) | (_, Err(e)) => println!("{}", e)
+        }
     }
 
     println!("root: {:?}", atlas.roots());
@@ -153,9 +286,139 @@ fn main() {
     println!("direct orbits: {:?}", atlas.direct_orbits());
     println!("indirect orbits: {:?}", atlas.indirect_orbits());
     println!("total orbits: {:?}", atlas.total_orbits());
-    println!(
-        "transfer from YOU to SAN: {:?}",
-        atlas.orbital_transfer(&"YOU".to_string(), &"SAN".to_string())
+    println!("deepest body: {:?}", atlas.deepest());
+    match atlas.orbital_transfer(&"YOU".to_string(), &"SAN".to_string()) {
+        Ok(transfer) => println!("transfer from YOU to SAN: {:?}", transfer),
+        Err(e) => println!("transfer from YOU to SAN: {}", e)
+    }
+}
+
+
+
+#[cfg(test)]
+fn test_atlas(input: &str) -> Atlas {
+    Atlas::parse(&input.split_whitespace().collect::<Vec<_>>().join("\n")).unwrap()
+}
+
+#[test]
+fn test_orbits() {
+    let atlas = test_atlas("COM)B B)C C)D D)E E)F B)G G)H D)I E)J J)K K)L K)YOU I)SAN");
+    assert_eq!(atlas.roots(), vec!["COM".to_string()]);
+    assert_eq!(atlas.total_orbits(), 42 + 7 + 5);
+    assert_eq!(atlas.orbital_transfer(&"YOU".to_string(), &"SAN".to_string()), Ok(Some(4)));
+    assert_eq!(atlas.orbital_transfer(&"COM".to_string(), &"SAN".to_string()), Ok(None));
+    assert_eq!(
+        atlas.orbital_transfer(&"YOU".to_string(), &"SANTA".to_string()),
+        Err(AtlasError::UnknownPlanet("SANTA".to_string()))
+    );
+
+    // the common ancestor matches walking up from both planets
+    let planets = &atlas.planets;
+    let up = |mut id: NodeId| {
+        let mut path = vec![id];
+        while let Some(parent) = planets.parent(id) {
+            path.push(parent);
+            id = parent;
+        }
+        path
+    };
+    for a in planets.ids() {
+        for b in planets.ids() {
+            let (up_a, up_b) = (up(a), up(b));
+            let distance = up_a.iter().position(|id| up_b.contains(id))
+                .map(|steps| steps + up_b.iter().position(|id| *id == up_a[steps]).unwrap());
+            assert_eq!(planets.distance(a, b), distance);
+        }
+    }
+}
+
+#[test]
+fn test_atlas_queries() {
+    let atlas = test_atlas("COM)B B)C C)D D)E E)F B)G G)H D)I E)J J)K K)L K)YOU I)SAN X)Y");
+    let id = |id: &str| id.to_string();
+    let names = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(atlas.transfer_path(&id("YOU"), &id("SAN")), Ok(Some(names(&["K", "J", "E", "D", "I"]))));
+    assert_eq!(atlas.transfer_path(&id("L"), &id("YOU")), Ok(Some(names(&["K"]))));
+    assert_eq!(atlas.transfer_path(&id("YOU"), &id("Y")), Ok(None));
+    assert_eq!(atlas.subtree_size(&id("COM")), Ok(14));
+    assert_eq!(atlas.subtree_size(&id("E")), Ok(6));
+    assert_eq!(atlas.subtree_size(&id("L")), Ok(1));
+    assert_eq!(atlas.deepest(), Some((id("L"), 7)));
+    assert_eq!(atlas.siblings(&id("L")), Ok(names(&["YOU"])));
+    assert_eq!(atlas.siblings(&id("C")), Ok(names(&["G"])));
+    assert_eq!(atlas.siblings(&id("X")), Ok(names(&["COM"])));
+    assert_eq!(atlas.common_ancestor(&names(&["YOU", "SAN", "F"])), Ok(Some(id("D"))));
+    assert_eq!(atlas.common_ancestor(&names(&["L"])), Ok(Some(id("L"))));
+    assert_eq!(atlas.common_ancestor(&names(&["L", "Y"])), Ok(None));
+    assert_eq!(atlas.common_ancestor(&names(&["L", "Z"])), Err(AtlasError::UnknownPlanet(id("Z"))));
+    assert_eq!(atlas.common_ancestor(&[]), Ok(None));
+
+    let atlas = test_atlas("COM)B B)D B)C X)Y");
+    assert_eq!(atlas.outline(), "COM\n  B\n    C\n    D\nX\n  Y\n");
+    assert_eq!(
+        atlas.to_dot(),
+        "digraph orbits {\n    \"COM\";\n    \"COM\" -> \"B\";\n    \"B\" -> \"C\";\n    \"B\" -> \"D\";\n    \"X\";\n    \"X\" -> \"Y\";\n}\n"
     );
 }
 
+#[test]
+fn test_atlas_errors() {
+    let error = |input: &str| Atlas::parse(input).err();
+
+    assert_eq!(error("A)B\n\nB)C\n"), None);
+    assert_eq!(error("A)B\nB)C)D"), Some(AtlasError::Malformed(2, "B)C)D".to_string())));
+    assert_eq!(error("A)B\nC"), Some(AtlasError::Malformed(2, "C".to_string())));
+    assert_eq!(error("A)"), Some(AtlasError::Malformed(1, "A)".to_string())));
+    assert_eq!(error("A)B C"), Some(AtlasError::Malformed(1, "A)B C".to_string())));
+    assert_eq!(error("A)B\nB)B"), Some(AtlasError::SelfOrbit(2, "B".to_string())));
+    assert_eq!(
+        error("A)B\nC)D\nC)B"),
+        Some(AtlasError::DuplicateParent(3, "B".to_string(), "A".to_string(), 1))
+    );
+
+    let cycle = error("A)B\nB)C\nX)Y\nC)A");
+    assert_eq!(cycle, Some(AtlasError::Cycle(4, vec!["A".to_string(), "C".to_string(), "B".to_string()])));
+    assert_eq!(cycle.unwrap().to_string(), "line 4: orbits form a cycle A -> C -> B");
+
+    // the cycle closed first is reported, bodies hanging below a cycle are not part of it
+    let names = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        error("P)Q\nQ)R\nE)F\nF)E\nR)S\nS)P\nCOM)A\nF)G"),
+        Some(AtlasError::Cycle(4, names(&["E", "F"])))
+    );
+    assert_eq!(error("S)T\nP)Q\nQ)R\nR)P\nT)U"), Some(AtlasError::Cycle(4, names(&["P", "R", "Q"]))));
+
+    // a long chain given top down loads without walking up on every line
+    let chain: Vec<String> = (0..20000).map(|x| format!("P{})P{}", x, x + 1)).collect();
+    let atlas = Atlas::parse(&chain.join("\n")).unwrap();
+    assert_eq!(atlas.total_orbits(), 20000 * 20001 / 2);
+}
+
+#[test]
+fn test_tree() {
+    let mut tree = Tree::new();
+    let ids: Vec<NodeId> = (0..12).map(|x| tree.add(x)).collect();
+    // two chains 0 - 1 - ... - 9 and 10 - 11, the second one added before its parent exists
+    tree.set_parent(ids[11], ids[10]);
+    for pair in ids[..10].windows(2) {
+        tree.set_parent(pair[1], pair[0]);
+    }
+
+    assert_eq!(tree.roots().collect::<Vec<_>>(), vec![0, 10]);
+    assert_eq!(tree.depth(9), 9);
+    assert_eq!(tree.root_of(9), 0);
+    assert_eq!(tree.ancestor(9, 7), Some(2));
+    assert_eq!(tree.ancestor(9, 10), None);
+    assert_eq!(tree.common_ancestor(3, 8), Some(3));
+    assert_eq!(tree.common_ancestor(8, 11), None);
+
+    // moving a subtree updates the depths of everything below it
+    tree.set_parent(5, 11);
+    assert_eq!(tree.children(4), &[] as &[NodeId]);
+    assert_eq!((tree.depth(9), tree.root_of(9)), (6, 10));
+    assert_eq!(tree.common_ancestor(9, 11), Some(11));
+    assert_eq!(tree.distance(9, 4), None);
+    assert_eq!(tree.distance(7, 10), Some(4));
+    assert_eq!(*tree.get(7), 7);
+}
@@ -0,0 +1,224 @@
+use std::cell::OnceCell;
+
+
+pub type NodeId = usize;
+
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    value: T,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>
+}
+
+// depths and ancestors, built in one pass on the first query after the tree changed
+#[derive(Debug, Clone)]
+struct Index {
+    depths: Vec<usize>,
+    // amount of nodes below every node including itself
+    sizes: Vec<usize>,
+    // `ancestors[k][id]` is the ancestor 2^k levels above `id`, roots are their own ancestors
+    ancestors: Vec<Vec<NodeId>>,
+    // nodes on a cycle or below one, none of the other fields are valid for them
+    unreachable: Vec<NodeId>
+}
+
+impl Index {
+    fn new<T>(nodes: &[Node<T>]) -> Self {
+        let mut depths = vec![0; nodes.len()];
+        let mut parents: Vec<NodeId> = (0..nodes.len()).collect();
+
+        // parents are visited before their children
+        let mut order = Vec::with_capacity(nodes.len());
+        let mut stack: Vec<NodeId> = (0..nodes.len()).filter(|id| nodes[*id].parent.is_none()).collect();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            for child in &nodes[id].children {
+                depths[*child] = depths[id] + 1;
+                parents[*child] = id;
+                stack.push(*child);
+            }
+        }
+
+        let mut reached = vec![false; nodes.len()];
+        for id in &order {
+            reached[*id] = true;
+        }
+        let unreachable = (0..nodes.len()).filter(|id| !reached[*id]).collect();
+
+        let mut sizes = vec![1; nodes.len()];
+        for id in order.iter().rev() {
+            if let Some(parent) = nodes[*id].parent {
+                sizes[parent] += sizes[*id];
+            }
+        }
+
+        let max_depth = depths.iter().cloned().max().unwrap_or(0);
+        let mut ancestors = vec![parents];
+        while 1 << ancestors.len() <= max_depth {
+            let last = ancestors.last().unwrap();
+            let next = last.iter().map(|id| last[*id]).collect();
+            ancestors.push(next);
+        }
+
+        Index { depths, sizes, ancestors, unreachable }
+    }
+
+    fn ancestor(&self, mut id: NodeId, levels: usize) -> NodeId {
+        for (k, ancestors) in self.ancestors.iter().enumerate() {
+            if levels & (1 << k) != 0 {
+                id = ancestors[id];
+            }
+        }
+        id
+    }
+}
+
+
+// nodes live in one vector and refer to each other by their position in it,
+// a tree can have several roots
+#[derive(Debug, Clone)]
+pub struct Tree<T> {
+    nodes: Vec<Node<T>>,
+    index: OnceCell<Index>
+}
+
+impl<T> Tree<T> {
+    pub fn new() -> Self {
+        Tree { nodes: Vec::new(), index: OnceCell::new() }
+    }
+
+    pub fn add(&mut self, value: T) -> NodeId {
+        self.nodes.push(Node { value, parent: None, children: Vec::new() });
+        self.index = OnceCell::new();
+        self.nodes.len() - 1
+    }
+
+    // moves `child` with everything below it, cycles are only detected by `unreachable`
+    pub fn set_parent(&mut self, child: NodeId, parent: NodeId) {
+        if let Some(previous) = self.nodes[child].parent {
+            self.nodes[previous].children.retain(|id| *id != child);
+        }
+        self.nodes[child].parent = Some(parent);
+        self.nodes[parent].children.push(child);
+        self.index = OnceCell::new();
+    }
+
+    pub fn get(&self, id: NodeId) -> &T {
+        &self.nodes[id].value
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item=NodeId> {
+        0..self.nodes.len()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id].children
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item=NodeId> + '_ {
+        self.ids().filter(move |id| self.nodes[*id].parent.is_none())
+    }
+
+    // nodes with the same parent, for a root the other roots
+    pub fn siblings(&self, id: NodeId) -> Vec<NodeId> {
+        match self.parent(id) {
+            Some(parent) => self.children(parent).iter().cloned().filter(|other| *other != id).collect(),
+            None => self.roots().filter(|other| *other != id).collect()
+        }
+    }
+
+    fn index(&self) -> &Index {
+        self.index.get_or_init(|| Index::new(&self.nodes))
+    }
+
+    // nodes no root leads to, every one of them is on a cycle or below one, the other queries
+    // are only valid while this is empty
+    pub fn unreachable(&self) -> &[NodeId] {
+        &self.index().unreachable
+    }
+
+    // amount of ancestors, 0 for a root
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.index().depths[id]
+    }
+
+    pub fn subtree_size(&self, id: NodeId) -> usize {
+        self.index().sizes[id]
+    }
+
+    // the first of the deepest nodes
+    pub fn deepest(&self) -> Option<NodeId> {
+        let depths = &self.index().depths;
+        (0..self.nodes.len()).rev().max_by_key(|id| depths[*id])
+    }
+
+    #[cfg(test)]
+    pub fn root_of(&self, id: NodeId) -> NodeId {
+        self.index().ancestor(id, self.depth(id))
+    }
+
+    // the ancestor `levels` above `id`, None above the root
+    #[cfg(test)]
+    pub fn ancestor(&self, id: NodeId, levels: usize) -> Option<NodeId> {
+        if levels > self.depth(id) {
+            return None;
+        }
+        Some(self.index().ancestor(id, levels))
+    }
+
+    // deepest node both are below of (or equal to), None for nodes of different roots
+    pub fn common_ancestor(&self, a: NodeId, b: NodeId) -> Option<NodeId> {
+        let index = self.index();
+        let (a, b) = if index.depths[a] > index.depths[b] { (a, b) } else { (b, a) };
+        let (mut a, mut b) = (index.ancestor(a, index.depths[a] - index.depths[b]), b);
+        if a == b {
+            return Some(a);
+        }
+
+        for ancestors in index.ancestors.iter().rev() {
+            if ancestors[a] != ancestors[b] {
+                a = ancestors[a];
+                b = ancestors[b];
+            }
+        }
+        let (a, b) = (index.ancestors[0][a], index.ancestors[0][b]);
+        if a == b { Some(a) } else { None }
+    }
+
+    pub fn common_ancestor_of(&self, ids: &[NodeId]) -> Option<NodeId> {
+        let (first, rest) = ids.split_first()?;
+        rest.iter().try_fold(*first, |ancestor, id| self.common_ancestor(ancestor, *id))
+    }
+
+    // nodes from `a` up to the common ancestor and down to `b`, both included
+    pub fn path(&self, a: NodeId, b: NodeId) -> Option<Vec<NodeId>> {
+        let ancestor = self.common_ancestor(a, b)?;
+        let up = |mut id: NodeId| {
+            let mut path = vec![id];
+            while id != ancestor {
+                id = self.nodes[id].parent.unwrap();
+                path.push(id);
+            }
+            path
+        };
+
+        let mut path = up(a);
+        path.extend(up(b).into_iter().rev().skip(1));
+        Some(path)
+    }
+
+    // edges between the nodes, going up to the common ancestor and back down
+    pub fn distance(&self, a: NodeId, b: NodeId) -> Option<usize> {
+        let ancestor = self.common_ancestor(a, b)?;
+        Some(self.depth(a) + self.depth(b) - 2 * self.depth(ancestor))
+    }
+}
@@ -1,140 +1,87 @@
+extern crate common;
 use std::fs;
 
-#[derive(Debug)]
-enum ImageError {
-    InvalidData()
-}
+mod sif;
 
-struct Image {
-    data: Vec<i32>,
-    width: usize,
-    height: usize,
-    depth: usize
-}
+use common::args::Args;
+use common::export::Raster;
+use common::ocr;
+use sif::{Codec, Composite, Theme};
 
-impl Image {
-    fn new(data: Vec<i32>, width: usize, height: usize) -> Result<Self, ImageError> {
-        let layer_len = width * height;
-        if layer_len <= 0 || data.len() % layer_len != 0 {
-            return Err(ImageError::InvalidData())
-        }
-        let layers = data.len() / layer_len;
-
-        Ok(Image {
-            data: data,
-            width: width,
-            height: height,
-            depth: layers
-        })
-    }
 
-    fn layers(&self) -> impl Iterator<Item=&[i32]> {
-        Layers::new(&self)
+fn main() {
+    // day08 [IMAGE] [--width N] [--height N] [--transparent N] [--theme dark|light|gray] [--encode FILE]
+    //       [--mode over|under|last] [--reshape WxH] [--crop X,Y,W,H] [--stats]
+    let args = Args::from_env();
+    let number = |name: &str, default: usize| args.option(name)
+        .map(|x| x.parse::<usize>().unwrap_or_else(|_| panic!("{} needs a number", name)))
+        .unwrap_or(default);
+    let output = args.positional();
+
+    let mut codec = Codec::new(number("--width", 25), number("--height", 6));
+    if let Some(value) = args.option("--transparent") {
+        let value = value.parse::<i32>().expect("invalid transparent value");
+        codec = codec.with_alphabet(&[0, 1, 2, value]).with_transparent(value);
     }
-
-    fn values<'a>(&'a self, x: usize, y: usize) -> impl Iterator<Item=i32> + 'a {
-        let position = x + y * self.width;
-        self.layers()
-            .map(move |layer| layer[position])
+    let mode = args.option("--mode").map(|name| Composite::parse(name).expect("unknown mode"))
+        .unwrap_or(Composite::Over);
+    let numbers = |name: &str, value: &str, separator: char| value.split(separator)
+        .map(|x| x.parse::<usize>().unwrap_or_else(|_| panic!("{} needs numbers", name)))
+        .collect::<Vec<usize>>();
+    let theme = args.option("--theme").map(|name| Theme::named(name).expect("unknown theme"))
+        .unwrap_or_else(|| Theme::named("dark").unwrap());
+
+    let input = fs::read_to_string("../input.txt").unwrap();
+    let image = codec.decode(&input).unwrap_or_else(|e| panic!("{}", e));
+
+    println!("1s * 2s of layer with min 0s: {}", image.checksum().unwrap());
+    println!();
+
+    let mut image = image;
+    if let Some(value) = args.option("--reshape") {
+        let size = numbers("--reshape", value, 'x');
+        assert_eq!(size.len(), 2, "--reshape needs WxH");
+        image = image.reshape(size[0], size[1]).unwrap_or_else(|e| panic!("{}", e));
     }
-
-    fn decoded_value(&self, x: usize, y: usize) -> Option<i32> {
-        self.values(x, y)
-            .filter(|value| *value != 2)
-            .nth(0)
+    if let Some(value) = args.option("--crop") {
+        let area = numbers("--crop", value, ',');
+        assert_eq!(area.len(), 4, "--crop needs X,Y,W,H");
+        image = image.crop(area[0], area[1], area[2], area[3]).unwrap_or_else(|e| panic!("{}", e));
     }
 
-    fn decoded(&self) -> Vec<i32> {
-        let mut result = Vec::with_capacity(self.width * self.height);
-        result.resize(self.width * self.height, 2);
-
-        for x in 0..self.width {
-            for y in 0..self.height {
-                if let Some(value) = self.decoded_value(x, y) {
-                    result[x + y * self.width] = value;
-                }
-            }
+    if args.flag("--stats") {
+        for (index, layer) in image.layer_stats(mode).iter().enumerate() {
+            println!("layer {}: {} pixels shown, values {:?}", index, layer.wins, layer.histogram);
         }
-
-        result
-    }
-}
-
-
-struct Layers<'a> {
-    image: &'a Image,
-    current_layer: usize
-}
-
-impl<'a> Iterator for Layers<'a> {
-    type Item = &'a [i32];
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_layer >= self.image.depth {
-            return None;
-        }
-
-        let layer_size = self.image.width * self.image.height;
-        let start = layer_size * self.current_layer;
-        let end = start + layer_size;
-
-        self.current_layer = self.current_layer + 1;
-
-        Some(&self.image.data[start..end])
+        // the last digit of the layer every pixel is taken from
+        let winners = image.winners(mode);
+        println!("{}", winners.render(|x| x.cloned().flatten()
+            .map(|layer| (b'0' + (layer % 10) as u8) as char)
+            .unwrap_or(' ')));
+        println!();
     }
-}
 
-impl<'a> Layers<'a> {
-    fn new(image: &'a Image) -> Self {
-        Layers { image: image, current_layer: 0 }
+    let decoded = image.composite(mode);
+    println!("{}", decoded.render(|x| theme.char(x)));
+    match ocr::recognize(&decoded, |x| *x == 1) {
+        Ok(text) => println!("decoded message: {}", text),
+        Err(e) => println!("unable to read message: {}", e)
     }
-}
-
 
-trait CountIf<T> {
-    fn count_if<P>(&self, predicate: P) -> usize
-        where P: FnMut(&T) -> bool;
-}
-
-impl<'a, T> CountIf<T> for &'a [T] {
-    fn count_if<P>(&self, mut predicate: P) -> usize
-        where P: FnMut(&T) -> bool
-    {
-        self.iter().filter(|x| predicate(x)).count()
+    // the decoded image as a stream with a single layer, after cropping and reshaping
+    if let Some(path) = args.option("--encode") {
+        match image.encode(&codec, mode) {
+            Ok(text) => {
+                fs::write(path, text).unwrap();
+                println!("saved encoded {}x{} image to {}", image.width(), image.height(), path);
+            },
+            Err(e) => println!("unable to encode image: {}", e)
+        }
     }
-}
-
 
-fn main() {
-    let input = fs::read_to_string("../input.txt").unwrap()
-        .trim()
-        .chars()
-        .map(|x| x.to_string().parse().unwrap())
-        .collect();
-
-    let image = Image::new(input, 25, 6).unwrap();
-
-    let a: &[i32] = image.layers()
-        .min_by_key(|layer| layer.count_if(|x| *x == 0))
-        .unwrap();
-
-    let ones = a.count_if(|x| *x == 1);
-    let twos = a.count_if(|x| *x == 2);
-
-    println!("1s * 2s of layer with min 0s: {}", ones * twos);
-    println!("");
-
-    let decoded = image.decoded();
-    for row in 0..image.height {
-        let start = row * image.width;
-        let end = start + image.width;
-        let data = &decoded[start..end];
-
-        let p: Vec<&str> = data.iter()
-            .map(|x| if *x == 0 { " " } else { "\u{2588}" })
-            .collect();
-
-        println!("{}", p.join(""));
+    // optionally archive the decoded image, the format is taken from the extension
+    if let Some(path) = output {
+        Raster::from_grid(&decoded, &theme.colors, 10).save(path).unwrap();
+        println!("saved decoded image to {}", path);
     }
 }
-
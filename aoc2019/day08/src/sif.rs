@@ -0,0 +1,476 @@
+use std::fmt;
+use std::collections::BTreeMap;
+
+use common::grid::Grid;
+use common::export::{Palette, Rgb, BLACK, WHITE};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    EmptyLayer,
+    // amount of values and the size of a layer
+    InvalidLength(usize, usize),
+    // offset in the stream and the character found there
+    InvalidDigit(usize, char),
+    // layer, offset in the layer and the value
+    InvalidValue(usize, usize, i32),
+    // x, y, width and height of an area outside of the image
+    InvalidCrop(usize, usize, usize, usize)
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::EmptyLayer => write!(f, "layers have no pixels"),
+            ImageError::InvalidLength(len, layer) => write!(f, "{} values are no multiple of the layer size {}", len, layer),
+            ImageError::InvalidDigit(offset, c) => write!(f, "invalid digit '{}' at {}", c, offset),
+            ImageError::InvalidValue(layer, offset, value) => write!(f, "invalid value {} at {} in layer {}", value, offset, layer),
+            ImageError::InvalidCrop(x, y, width, height) => write!(f, "{}x{} at {},{} is outside of the image", width, height, x, y)
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Composite {
+    // the first value which isn't transparent, looking from the front
+    Over,
+    // the first value which isn't transparent, looking from the back
+    Under,
+    // the last layer on its own, transparent pixels stay transparent, see Under to look through them
+    LastLayer
+}
+
+impl Composite {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "over" => Some(Composite::Over),
+            "under" => Some(Composite::Under),
+            "last" => Some(Composite::LastLayer),
+            _ => None
+        }
+    }
+}
+
+
+// values of a layer and how many pixels of the composited image it provides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerStats {
+    pub histogram: BTreeMap<i32, usize>,
+    pub wins: usize
+}
+
+
+// layers of width * height values, the first layer is in front
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    data: Vec<i32>,
+    width: usize,
+    height: usize,
+    depth: usize,
+    transparent: i32
+}
+
+impl Image {
+    pub fn new(data: Vec<i32>, width: usize, height: usize) -> Result<Self, ImageError> {
+        let layer_len = width * height;
+        if layer_len == 0 {
+            return Err(ImageError::EmptyLayer);
+        }
+        if data.len() % layer_len != 0 {
+            return Err(ImageError::InvalidLength(data.len(), layer_len));
+        }
+        let layers = data.len() / layer_len;
+
+        Ok(Image {
+            data,
+            width,
+            height,
+            depth: layers,
+            transparent: 2
+        })
+    }
+
+    // value which lets the layers behind it show through
+    pub fn with_transparent(mut self, value: i32) -> Self {
+        self.transparent = value;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item=&[i32]> {
+        Layers::new(self)
+    }
+
+    // pixels which are transparent on every layer stay transparent
+    pub fn composite(&self, mode: Composite) -> Grid<i32> {
+        let (pixels, _) = self.composite_pixels(mode);
+        Grid::from_rows(pixels.chunks(self.width).map(|row| row.to_vec()))
+    }
+
+    // the composited image as a stream with a single layer, sized like the image rather than `codec`
+    pub fn encode(&self, codec: &Codec, mode: Composite) -> Result<String, ImageError> {
+        let (pixels, _) = self.composite_pixels(mode);
+        codec.clone().with_size(self.width, self.height).encode(&[pixels])
+    }
+
+    // the layer every pixel of the composited image comes from, None where all layers are transparent
+    pub fn winners(&self, mode: Composite) -> Grid<Option<usize>> {
+        let (_, winners) = self.composite_pixels(mode);
+        Grid::from_rows(winners.chunks(self.width).map(|row| row.to_vec()))
+    }
+
+    // one pass over the layers in the order they are stored, every layer is read front to back
+    fn composite_pixels(&self, mode: Composite) -> (Vec<i32>, Vec<Option<usize>>) {
+        let size = self.width * self.height;
+        let mut pixels = vec![self.transparent; size];
+        let mut winners = vec![None; size];
+
+        let mut layers: Vec<(usize, &[i32])> = self.layers().enumerate().collect();
+        match mode {
+            Composite::Over => (),
+            Composite::Under => layers.reverse(),
+            Composite::LastLayer => {
+                let last = layers.pop();
+                layers = last.into_iter().collect();
+            }
+        }
+
+        let mut open = size;
+        for (index, layer) in layers {
+            for (position, value) in layer.iter().enumerate() {
+                let see_through = *value == self.transparent && mode != Composite::LastLayer;
+                if winners[position].is_none() && !see_through {
+                    pixels[position] = *value;
+                    winners[position] = Some(index);
+                    open -= 1;
+                }
+            }
+            if open == 0 {
+                break;
+            }
+        }
+
+        (pixels, winners)
+    }
+
+    pub fn layer_stats(&self, mode: Composite) -> Vec<LayerStats> {
+        let mut stats: Vec<LayerStats> = self.layers()
+            .map(|layer| {
+                let mut histogram = BTreeMap::new();
+                for value in layer {
+                    *histogram.entry(*value).or_insert(0) += 1;
+                }
+                LayerStats { histogram, wins: 0 }
+            })
+            .collect();
+
+        for winner in self.composite_pixels(mode).1.into_iter().flatten() {
+            stats[winner].wins += 1;
+        }
+        stats
+    }
+
+    // the same area of every layer
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Result<Image, ImageError> {
+        if width == 0 || height == 0 || x + width > self.width || y + height > self.height {
+            return Err(ImageError::InvalidCrop(x, y, width, height));
+        }
+
+        let data = self.layers()
+            .flat_map(|layer| layer.chunks(self.width)
+                .skip(y)
+                .take(height)
+                .flat_map(|row| row[x..x + width].iter().cloned()))
+            .collect();
+        Ok(Image::new(data, width, height)?.with_transparent(self.transparent))
+    }
+
+    // the same stream read with other dimensions, the layer size may change as well
+    pub fn reshape(&self, width: usize, height: usize) -> Result<Image, ImageError> {
+        Ok(Image::new(self.data.clone(), width, height)?.with_transparent(self.transparent))
+    }
+
+    pub fn layer_with_fewest(&self, value: i32) -> Option<&[i32]> {
+        self.layers().min_by_key(|layer| layer.count_if(|x| *x == value))
+    }
+
+    // ones times twos of the layer with the fewest zeros
+    pub fn checksum(&self) -> Option<usize> {
+        let layer = self.layer_with_fewest(0)?;
+        Some(layer.count_if(|x| *x == 1) * layer.count_if(|x| *x == 2))
+    }
+}
+
+
+struct Layers<'a> {
+    image: &'a Image,
+    current_layer: usize
+}
+
+impl<'a> Iterator for Layers<'a> {
+    type Item = &'a [i32];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_layer >= self.image.depth {
+            return None;
+        }
+
+        let layer_size = self.image.width * self.image.height;
+        let start = layer_size * self.current_layer;
+        let end = start + layer_size;
+
+        self.current_layer += 1;
+
+        Some(&self.image.data[start..end])
+    }
+}
+
+impl<'a> Layers<'a> {
+    fn new(image: &'a Image) -> Self {
+        Layers { image, current_layer: 0 }
+    }
+}
+
+
+trait CountIf<T> {
+    fn count_if<P>(&self, predicate: P) -> usize
+        where P: FnMut(&T) -> bool;
+}
+
+impl<T> CountIf<T> for &[T] {
+    fn count_if<P>(&self, mut predicate: P) -> usize
+        where P: FnMut(&T) -> bool
+    {
+        self.iter().filter(|x| predicate(x)).count()
+    }
+}
+
+
+// reads and writes images as one digit per value, layer after layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Codec {
+    pub width: usize,
+    pub height: usize,
+    alphabet: Vec<i32>,
+    transparent: i32
+}
+
+impl Codec {
+    // black, white and transparent
+    pub fn new(width: usize, height: usize) -> Self {
+        Codec { width, height, alphabet: vec![0, 1, 2], transparent: 2 }
+    }
+
+    // digits which may appear in a stream, the transparent value is always allowed
+    pub fn with_alphabet(mut self, alphabet: &[i32]) -> Self {
+        self.alphabet = alphabet.to_vec();
+        self
+    }
+
+    pub fn with_transparent(mut self, value: i32) -> Self {
+        self.transparent = value;
+        self
+    }
+
+    pub fn with_size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    fn is_allowed(&self, value: i32) -> bool {
+        value == self.transparent || self.alphabet.contains(&value)
+    }
+
+    // whitespace around the stream is ignored
+    pub fn decode(&self, text: &str) -> Result<Image, ImageError> {
+        let start = text.len() - text.trim_start().len();
+        let data = text.trim().chars()
+            .enumerate()
+            .map(|(offset, c)| c.to_digit(10)
+                .map(|digit| digit as i32)
+                .filter(|digit| self.is_allowed(*digit))
+                .ok_or(ImageError::InvalidDigit(start + offset, c)))
+            .collect::<Result<Vec<i32>, ImageError>>()?;
+
+        Ok(Image::new(data, self.width, self.height)?.with_transparent(self.transparent))
+    }
+
+    pub fn encode<L: AsRef<[i32]>>(&self, layers: &[L]) -> Result<String, ImageError> {
+        let layer_len = self.width * self.height;
+        if layer_len == 0 {
+            return Err(ImageError::EmptyLayer);
+        }
+
+        let mut text = String::with_capacity(layers.len() * layer_len);
+        for (index, layer) in layers.iter().enumerate() {
+            let layer = layer.as_ref();
+            if layer.len() != layer_len {
+                return Err(ImageError::InvalidLength(layer.len(), layer_len));
+            }
+            for (offset, value) in layer.iter().enumerate() {
+                if !(0..10).contains(value) || !self.is_allowed(*value) {
+                    return Err(ImageError::InvalidValue(index, offset, *value));
+                }
+                text.push((b'0' + *value as u8) as char);
+            }
+        }
+        Ok(text)
+    }
+}
+
+
+// how decoded values are shown in the terminal and in exported images
+#[derive(Debug, Clone)]
+pub struct Theme {
+    chars: Vec<(i32, char)>,
+    background: char,
+    pub colors: Palette<i32>
+}
+
+impl Theme {
+    pub fn new(background: char, color: Rgb) -> Self {
+        Theme { chars: Vec::new(), background, colors: Palette::new(color) }
+    }
+
+    pub fn with(mut self, value: i32, c: char, color: Rgb) -> Self {
+        self.chars.retain(|(v, _)| *v != value);
+        self.chars.push((value, c));
+        self.colors = self.colors.with(value, color);
+        self
+    }
+
+    pub fn char(&self, value: Option<&i32>) -> char {
+        value.and_then(|value| self.chars.iter().find(|(v, _)| v == value))
+            .map(|(_, c)| *c)
+            .unwrap_or(self.background)
+    }
+
+    // dark, light or gray, all of them with zero black, one white and two transparent
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::new(' ', BLACK)
+                .with(0, ' ', BLACK)
+                .with(1, '\u{2588}', WHITE)
+                .with(2, '\u{2591}', (128, 128, 128))),
+            "light" => Some(Theme::new('\u{2588}', WHITE)
+                .with(0, '\u{2588}', BLACK)
+                .with(1, ' ', WHITE)
+                .with(2, '\u{2591}', (192, 192, 192))),
+            "gray" => Some(Theme::new(' ', (64, 64, 64))
+                .with(0, '\u{2592}', (64, 64, 64))
+                .with(1, '\u{2588}', (224, 224, 224))
+                .with(2, ' ', (128, 128, 128))),
+            _ => None
+        }
+    }
+}
+
+
+#[test]
+fn test_codec() {
+    let codec = Codec::new(2, 2);
+    let image = codec.decode("0222112222120000\n").unwrap();
+    assert_eq!(image.layers().count(), 4);
+    assert_eq!(image.composite(Composite::Over).render(|x| if x == Some(&1) { '#' } else { '.' }), ".#\n#.");
+    assert_eq!(codec.encode(&image.layers().collect::<Vec<_>>()), Ok("0222112222120000".to_string()));
+    assert_eq!(codec.encode(&[vec![0, 1, 1, 0]]), Ok("0110".to_string()));
+
+    assert_eq!(codec.decode("0123"), Err(ImageError::InvalidDigit(3, '3')));
+    assert_eq!(codec.decode(" 01x2"), Err(ImageError::InvalidDigit(3, 'x')));
+    assert_eq!(codec.decode("012"), Err(ImageError::InvalidLength(3, 4)));
+    assert_eq!(Codec::new(0, 3).decode(""), Err(ImageError::EmptyLayer));
+    assert_eq!(codec.encode(&[vec![0, 1, 1]]), Err(ImageError::InvalidLength(3, 4)));
+    assert_eq!(codec.encode(&[vec![0, 1, 1, 0], vec![0, 5, 1, 0]]), Err(ImageError::InvalidValue(1, 1, 5)));
+
+    // nine is see-through, two is a color
+    let codec = Codec::new(2, 1).with_alphabet(&[0, 1, 2]).with_transparent(9);
+    let image = codec.decode("9992").unwrap();
+    assert_eq!(image.winners(Composite::Over).get((0, 0)), Some(&None));
+    assert_eq!(image.composite(Composite::Over).get((1, 0)), Some(&2));
+    assert_eq!(codec.encode(&[vec![9, 12]]), Err(ImageError::InvalidValue(0, 1, 12)));
+}
+
+#[test]
+fn test_composite() {
+    // three layers of 3x1: the middle pixel is transparent on the front layer only,
+    // the last one everywhere but on the back layer
+    let image = Codec::new(3, 1).decode(concat!("022", "102", "211")).unwrap();
+
+    assert_eq!(image.composite(Composite::Over).render(|x| (b'0' + *x.unwrap() as u8) as char), "001");
+    assert_eq!(image.composite(Composite::Under).render(|x| (b'0' + *x.unwrap() as u8) as char), "111");
+    assert_eq!(image.composite(Composite::LastLayer).render(|x| (b'0' + *x.unwrap() as u8) as char), "211");
+    assert_eq!(image.winners(Composite::Over).row(0).map(|x| *x.unwrap()).collect::<Vec<_>>(), vec![Some(0), Some(1), Some(2)]);
+    assert_eq!(image.winners(Composite::Under).row(0).map(|x| *x.unwrap()).collect::<Vec<_>>(), vec![Some(1), Some(2), Some(2)]);
+
+    let stats = image.layer_stats(Composite::Over);
+    assert_eq!(stats.iter().map(|layer| layer.wins).collect::<Vec<_>>(), vec![1, 1, 1]);
+    assert_eq!(stats[2].histogram, vec![(1, 2), (2, 1)].into_iter().collect());
+
+    let image = Codec::new(1, 1).decode("22").unwrap();
+    assert_eq!(image.winners(Composite::Over).get((0, 0)), Some(&None));
+    assert_eq!(image.layer_stats(Composite::Under)[1].wins, 0);
+
+    // a transparent pixel on the last layer is kept as it is, only Under looks through it
+    let image = Codec::new(2, 1).decode(concat!("01", "21")).unwrap();
+    assert_eq!(image.composite(Composite::LastLayer).render(|x| (b'0' + *x.unwrap() as u8) as char), "21");
+    assert_eq!(image.composite(Composite::Under).render(|x| (b'0' + *x.unwrap() as u8) as char), "01");
+    assert_eq!(image.winners(Composite::LastLayer).row(0).map(|x| *x.unwrap()).collect::<Vec<_>>(), vec![Some(1), Some(1)]);
+}
+
+#[test]
+fn test_crop_and_reshape() {
+    let codec = Codec::new(3, 2).with_alphabet(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    let image = codec.decode(concat!("012345", "678901")).unwrap();
+
+    let cropped = image.crop(1, 0, 2, 2).unwrap();
+    assert_eq!(cropped.layers().collect::<Vec<_>>(), vec![&[1, 2, 4, 5][..], &[7, 8, 0, 1][..]]);
+    assert_eq!(image.crop(2, 1, 1, 1).unwrap().layers().count(), 2);
+    assert_eq!(image.crop(2, 1, 2, 1), Err(ImageError::InvalidCrop(2, 1, 2, 1)));
+    assert_eq!(image.crop(0, 0, 0, 1), Err(ImageError::InvalidCrop(0, 0, 0, 1)));
+
+    let reshaped = image.reshape(2, 3).unwrap();
+    assert_eq!(reshaped.composite(Composite::Over).render(|x| (b'0' + *x.unwrap() as u8) as char), "01\n83\n45");
+    assert_eq!(image.reshape(4, 1).unwrap().layers().count(), 3);
+    assert_eq!(image.reshape(5, 1), Err(ImageError::InvalidLength(12, 5)));
+}
+
+#[test]
+fn test_encode() {
+    let codec = Codec::new(3, 2);
+    let image = codec.decode(concat!("221022", "110001")).unwrap();
+    assert_eq!(image.encode(&codec, Composite::Over), Ok("111001".to_string()));
+
+    // the stream takes the size of the cropped image, not the one it was decoded with
+    let cropped = image.crop(1, 0, 2, 2).unwrap();
+    assert_eq!((cropped.width(), cropped.height()), (2, 2));
+    let text = cropped.encode(&codec, Composite::Over).unwrap();
+    assert_eq!(text, "1101");
+    let decoded = Codec::new(2, 2).decode(&text).unwrap();
+    assert_eq!(decoded.composite(Composite::Over).render(|x| (b'0' + *x.unwrap() as u8) as char), "11\n01");
+
+    let reshaped = image.reshape(2, 3).unwrap();
+    assert_eq!(reshaped.encode(&codec, Composite::Over), Ok("111001".to_string()));
+    assert_eq!(Codec::new(2, 3).decode("111001").unwrap().width(), 2);
+
+    // values of another alphabet are still rejected
+    let image = Codec::new(2, 1).with_alphabet(&[0, 1, 7]).decode("7012").unwrap();
+    assert_eq!(image.encode(&codec, Composite::Over), Err(ImageError::InvalidValue(0, 0, 7)));
+}
+
+#[test]
+fn test_checksum() {
+    let image = Codec::new(3, 2).with_alphabet(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).decode("123456789012").unwrap();
+    assert_eq!(image.checksum(), Some(1));
+    let image = Codec::new(2, 2).with_alphabet(&[0, 1, 2, 3]).decode("0012112101230000").unwrap();
+    assert_eq!(image.layer_with_fewest(0), Some(&[1, 1, 2, 1][..]));
+    assert_eq!(image.checksum(), Some(3));
+}
@@ -1,24 +1,20 @@
+extern crate common;
 use std::fs;
 use std::fmt;
-use std::cmp;
-use std::collections::HashSet;
+use std::collections::HashMap;
+
+use common::args::Args;
+use common::grid::Grid;
 
 
 #[derive(Debug)]
 struct Starfield {
-    data: Vec<Vec<bool>>
+    data: Grid<bool>
 }
 
 impl fmt::Display for Starfield {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let result = self.data.iter()
-            .map(|line| line.iter()
-                 .map(|x| if *x { "#" } else { "." })
-                 .collect::<Vec<&str>>()
-                 .join("")
-            )
-            .collect::<Vec<String>>()
-            .join("\n");
+        let result = self.data.render(|x| if x == Some(&true) { '#' } else { '.' });
 
         f.write_str(result.as_str())
     }
@@ -26,116 +22,192 @@ impl fmt::Display for Starfield {
 
 impl Starfield {
     fn from_string(field: &String) -> Self {
-        let data = field.lines()
-            .map(|line| line.chars().map(|x| x == '#').collect())
-            .collect();
+        let data = Grid::parse(field, |x| Some(x == '#'));
 
         Starfield { data: data }
     }
 
     fn asteroids<'a>(&'a self) -> impl Iterator<Item=(usize, usize)> + 'a {
-        self.data.iter()
-            .enumerate()
-            .flat_map(|(row_num, row)| row.iter()
-                      .enumerate()
-                      .filter(|(_, column)| **column)
-                      .map(move |(column_num, _)| (column_num, row_num))
-            )
+        self.data.positions_of(true)
+            .map(|(column_num, row_num)| (column_num as usize, row_num as usize))
     }
 
-    fn visible(&self, asteroid: (usize, usize)) -> Vec<(usize, usize)> {
-        let asteroids: HashSet<_> = self.asteroids().collect();
-
-        let reference = (asteroid.0 as i32, asteroid.1 as i32);
-
-        let mut visible = Vec::new();
-        for ta in asteroids.clone() {
-            if ta == asteroid {
-                continue;
-            }
-
-            let target = (ta.0 as i32, ta.1 as i32);
+    // the closest asteroid in every direction, sorted by position
+    // every other asteroid grouped by its direction from `station`
+    fn lines_of_sight(&self, station: (usize, usize)) -> LinesOfSight {
+        let reference = (station.0 as i32, station.1 as i32);
 
-            let blocker = asteroids.iter()
-                .map(|blocker| (blocker.0 as i32, blocker.1 as i32))
-                .filter(|blocker| *blocker != reference && *blocker != target)
-                .filter(|blocker| is_blocked_by(reference, target, *blocker))
-                .nth(0);
-
-            if blocker.is_none() {
-                visible.push(ta);
-            }
+        let mut lines = HashMap::new();
+        for target in self.asteroids().filter(|target| *target != station) {
+            let (direction, steps) = direction(reference, (target.0 as i32, target.1 as i32));
+            lines.entry(direction).or_insert_with(Vec::new).push((steps, target));
         }
+        lines
+    }
 
+    fn visible(&self, asteroid: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut visible: Vec<(usize, usize)> = self.lines_of_sight(asteroid)
+            .values()
+            .filter_map(|targets| targets.iter().min().map(|(_, target)| *target))
+            .collect();
+        visible.sort();
         visible
     }
 
-    fn destroy(&mut self, asteroid: (usize, usize)) {
-        self.data[asteroid.1][asteroid.0] = false;
+    // every other asteroid in the order a laser at `station` hits them, clockwise starting upwards
+    fn vaporization_order(&self, station: (usize, usize)) -> VaporizationOrder {
+        let lines = self.lines_of_sight(station)
+            .into_iter()
+            .map(|(direction, mut targets)| {
+                targets.sort();
+                (direction, targets.into_iter().map(|(_, target)| target).collect())
+            })
+            .collect();
+        VaporizationOrder::new(lines)
     }
 }
 
 
-fn is_blocked_by(reference: (i32, i32), target: (i32, i32), blocker: (i32, i32)) -> bool {
-    // in one line (X is same)
-    if reference.0 == target.0 && reference.0 == blocker.0 {
-        // blocker is between reference and target
-        return between(blocker.1, reference.1, target.1);
-    }
-    // in one line (Y is same)
-    if reference.1 == target.1 && reference.1 == blocker.1 {
-        // blocker is between reference and target
-        return between(blocker.0, reference.0, target.0);
-    }
-
-    let rx: f32 = reference.0 as f32;
-    let ry: f32 = reference.1 as f32;
-    let tx: f32 = target.0 as f32;
-    let ty: f32 = target.1 as f32;
-    let bx: f32 = blocker.0 as f32;
-    let by: f32 = blocker.1 as f32;
+// reduced directions from a station with the asteroids on them and their distance in steps
+type LinesOfSight = HashMap<(i32, i32), Vec<(i32, (usize, usize))>>;
 
-    let m = (ty - ry) / (tx - rx);
-    let n = (ry*tx - ty * rx) / (tx - rx);
+// a direction from the station with the asteroids on it, closest first
+type Line = ((i32, i32), Vec<(usize, usize)>);
 
-    // println!("m: {} | n: {} | angle: {}", m, n, angle(reference, target));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rotation {
+    Clockwise,
+    CounterClockwise
+}
 
-    let on_line = ((m*bx + n) - by).abs() < 0.01;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Vaporization {
+    asteroid: (usize, usize),
+    // the turn of the laser the asteroid is hit in, starting with 1
+    rotation: usize
+}
 
-    on_line
-        && between(blocker.0, reference.0, target.0)
-        && between(blocker.1, reference.1, target.1)
+// the laser hits the closest asteroid of every line per turn, the field itself is left untouched
+#[derive(Debug, Clone)]
+struct VaporizationOrder {
+    lines: Vec<Line>,
+    rotation: Rotation,
+    start: (i32, i32),
+    turn: usize,
+    line: usize
 }
 
+impl VaporizationOrder {
+    fn new(lines: Vec<Line>) -> Self {
+        let order = VaporizationOrder {
+            lines,
+            rotation: Rotation::Clockwise,
+            start: (0, -1),
+            turn: 0,
+            line: 0
+        };
+        order.sorted()
+    }
 
+    fn rotating(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self.sorted()
+    }
+
+    // the direction the laser points to first, asteroids exactly in that direction are hit first
+    fn starting_at(mut self, start: (i32, i32)) -> Self {
+        assert_ne!(start, (0, 0), "the laser needs a direction");
+        self.start = direction((0, 0), start).0;
+        self.sorted()
+    }
 
-fn angle(reference: (i32, i32), target: (i32, i32)) -> f32 {
-    let rx: f32 = reference.0 as f32;
-    let ry: f32 = reference.1 as f32;
-    let tx: f32 = target.0 as f32;
-    let ty: f32 = target.1 as f32;
+    // orders the lines by the angle from `start`, exactly with cross products instead of atan2
+    fn sorted(mut self) -> Self {
+        // y grows downwards, so a positive cross product turns clockwise on screen,
+        // products of a large start direction don't fit into an i32
+        let sign = if self.rotation == Rotation::Clockwise { 1 } else { -1 };
+        let wide = |a: (i32, i32)| (a.0 as i64, a.1 as i64);
+        let cross = |a: (i32, i32), b: (i32, i32)| {
+            let (a, b) = (wide(a), wide(b));
+            sign * (a.0 * b.1 - a.1 * b.0)
+        };
+        let start = self.start;
+        // 0 for the first half turn including the start itself, 1 for the second
+        let half = |d: (i32, i32)| {
+            let turn = cross(start, d);
+            let (s, d) = (wide(start), wide(d));
+            if turn > 0 || (turn == 0 && s.0 * d.0 + s.1 * d.1 > 0) { 0 } else { 1 }
+        };
+
+        self.lines.sort_by(|(a, _), (b, _)| half(*a).cmp(&half(*b)).then_with(|| 0.cmp(&cross(*a, *b))));
+        self.turn = 0;
+        self.line = 0;
+        self
+    }
+}
 
-    // move angles so 0 is "top"
-    let r = f32::atan2(ty - ry, tx - rx) + (std::f32::consts::PI / 2.);
+impl Iterator for VaporizationOrder {
+    type Item = Vaporization;
+
+    fn next(&mut self) -> Option<Vaporization> {
+        loop {
+            if self.line == self.lines.len() {
+                // a turn without hits means there is nothing left
+                if self.lines.iter().all(|(_, targets)| targets.len() <= self.turn) {
+                    return None;
+                }
+                self.turn += 1;
+                self.line = 0;
+            }
 
-    if r < 0. {
-        // fix the ordering so top left is actually last
-        r + (std::f32::consts::PI * 2.)
-    } else {
-        r
+            let targets = &self.lines[self.line].1;
+            self.line += 1;
+            if let Some(asteroid) = targets.get(self.turn) {
+                return Some(Vaporization { asteroid: *asteroid, rotation: self.turn + 1 });
+            }
+        }
     }
 }
 
 
-fn between(num: i32, a: i32, b: i32) -> bool {
-    num >= cmp::min(a, b) && num <= cmp::max(a, b)
+// smallest integer step from `reference` towards `target` and how many of them it takes
+fn direction(reference: (i32, i32), target: (i32, i32)) -> ((i32, i32), i32) {
+    let (dx, dy) = (target.0 - reference.0, target.1 - reference.1);
+    match gcd(dx.abs(), dy.abs()) {
+        0 => ((0, 0), 0),
+        steps => ((dx / steps, dy / steps), steps)
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// `blocker` lies on the line of sight from `reference` to `target`
+#[cfg(test)]
+fn is_blocked_by(reference: (i32, i32), target: (i32, i32), blocker: (i32, i32)) -> bool {
+    let (towards_target, target_steps) = direction(reference, target);
+    let (towards_blocker, blocker_steps) = direction(reference, blocker);
+    towards_target == towards_blocker && blocker_steps < target_steps
 }
 
 
 fn main() {
+    // day10 [--order] [--counterclockwise] [--start X,Y]
+    let args = Args::from_env();
+    let start = args.option("--start").map(|value| {
+        let parts: Vec<i32> = value.split(',').map(|x| x.parse().expect("invalid direction")).collect();
+        assert_eq!(parts.len(), 2, "--start needs X,Y");
+        (parts[0], parts[1])
+    });
+    if start == Some((0, 0)) {
+        println!("--start needs a direction, 0,0 doesn't point anywhere");
+        return;
+    }
+
     let input = fs::read_to_string("../input.txt").unwrap();
 
-    let mut starfield = Starfield::from_string(&input);
+    let starfield = Starfield::from_string(&input);
 
     let ideal_astroid = starfield.asteroids()
         .max_by_key(|a| starfield.visible(*a).len())
@@ -145,31 +217,21 @@ fn main() {
     println!("ideal asteroid: {:?}", ideal_astroid);
     println!("visible asteroids: {:?}", starfield.visible(ideal_astroid).len());
 
-    let mut i = 0;
-    let mut result = None;
-    loop {
-        let mut f = starfield.visible(ideal_astroid);
-        f.sort_by(|a, b| {
-            let ideal = (ideal_astroid.0 as i32, ideal_astroid.1 as i32);
-            let angle_a = angle(ideal, (a.0 as i32, a.1 as i32));
-            let angle_b = angle(ideal, (b.0 as i32, b.1 as i32));
-
-            angle_a.partial_cmp(&angle_b).unwrap()
-        });
-
-        for a in f {
-            starfield.destroy(a);
-            i = i + 1;
-            if i == 200 {
-                result = Some(a);
-            }
-        }
+    let mut order = starfield.vaporization_order(ideal_astroid);
+    if args.flag("--counterclockwise") {
+        order = order.rotating(Rotation::CounterClockwise);
+    }
+    if let Some(start) = start {
+        order = order.starting_at(start);
+    }
 
-        if starfield.asteroids().nth(1).is_none() {
-            break;
+    if args.flag("--order") {
+        for (i, vaporization) in order.clone().enumerate() {
+            println!("{}: {:?} in rotation {}", i + 1, vaporization.asteroid, vaporization.rotation);
         }
     }
 
+    let result = order.nth(199).map(|vaporization| vaporization.asteroid);
     println!("200th destroyed asteroid: {:?}", result);
 }
 
@@ -190,3 +252,93 @@ fn test() {
     assert_eq!(is_blocked_by((1, 1), (9, 9), (6, 6)), true);
 }
 
+
+#[test]
+fn test_visible() {
+    // the asteroids visible from a station are the ones nothing is blocking
+    let mut field = String::new();
+    let mut seed: u32 = 7;
+    for _ in 0..30 {
+        for _ in 0..30 {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            field.push(if (seed >> 16) % 3 == 0 { '#' } else { '.' });
+        }
+        field.push('\n');
+    }
+    let starfield = Starfield::from_string(&field);
+    let asteroids: Vec<(i32, i32)> = starfield.asteroids().map(|(x, y)| (x as i32, y as i32)).collect();
+    assert!(asteroids.len() > 250);
+
+    for station in asteroids.iter().step_by(25) {
+        let expected: Vec<(usize, usize)> = asteroids.iter()
+            .filter(|target| *target != station)
+            .filter(|target| !asteroids.iter().any(|blocker| blocker != *target && is_blocked_by(*station, **target, *blocker)))
+            .map(|(x, y)| (*x as usize, *y as usize))
+            .collect();
+        let mut visible = starfield.visible((station.0 as usize, station.1 as usize));
+        visible.sort_by_key(|(x, y)| (*y, *x));
+        assert_eq!(visible, expected);
+    }
+
+    let starfield = Starfield::from_string(&".#..#\n.....\n#####\n....#\n...##".to_string());
+    assert_eq!(starfield.visible((3, 4)).len(), 8);
+    assert_eq!(starfield.visible((4, 2)).len(), 5);
+}
+
+#[test]
+fn test_vaporization_order() {
+    let field = "\
+        .#..##.###...#######\n##.############..##.\n.#.######.########.#\n.###.#######.####.#.\n\
+        #####.##.#.##.###.##\n..#####..#.#########\n####################\n#.####....###.#.#.##\n\
+        ##.#################\n#####.##.###..####..\n..######..##.#######\n####.##.####...##..#\n\
+        .#####..#.######.###\n##...#.##########...\n#.##########.#######\n.####.#.###.###.#.##\n\
+        ....##.##.###..#####\n.#.#.###########.###\n#.#.#.#####.####.###\n###.##.####.##.#..##";
+    let starfield = Starfield::from_string(&field.to_string());
+    assert_eq!(starfield.visible((11, 13)).len(), 210);
+
+    let order: Vec<Vaporization> = starfield.vaporization_order((11, 13)).collect();
+    assert_eq!(order.len(), starfield.asteroids().count() - 1);
+    let nth = |n: usize| order[n - 1].asteroid;
+    assert_eq!((nth(1), nth(2), nth(3), nth(10), nth(20)), ((11, 12), (12, 1), (12, 2), (12, 8), (16, 0)));
+    assert_eq!((nth(50), nth(100), nth(199), nth(200), nth(201)), ((16, 9), (10, 16), (9, 6), (8, 2), (10, 9)));
+    assert_eq!(nth(299), (11, 1));
+    // the first turn hits every visible asteroid
+    assert_eq!(order[209].rotation, 1);
+    assert_eq!(order[210].rotation, 2);
+
+    let starfield = Starfield::from_string(&"###\n#.#\n###".to_string());
+    let positions = |order: VaporizationOrder| order.map(|x| x.asteroid).collect::<Vec<_>>();
+    assert_eq!(
+        positions(starfield.vaporization_order((1, 1))),
+        vec![(1, 0), (2, 0), (2, 1), (2, 2), (1, 2), (0, 2), (0, 1), (0, 0)]
+    );
+    assert_eq!(
+        positions(starfield.vaporization_order((1, 1)).rotating(Rotation::CounterClockwise)),
+        vec![(1, 0), (0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0)]
+    );
+    assert_eq!(
+        positions(starfield.vaporization_order((1, 1)).starting_at((1, 0))),
+        vec![(2, 1), (2, 2), (1, 2), (0, 2), (0, 1), (0, 0), (1, 0), (2, 0)]
+    );
+    // a start between two lines begins with the next one
+    assert_eq!(positions(starfield.vaporization_order((1, 1)).starting_at((1, -2)))[0], (2, 0));
+    // the start is reduced, large ones don't overflow while sorting
+    assert_eq!(
+        positions(starfield.vaporization_order((1, 1)).starting_at((4, 0))),
+        positions(starfield.vaporization_order((1, 1)).starting_at((1, 0)))
+    );
+    assert_eq!(
+        positions(starfield.vaporization_order((1, 1)).starting_at((2000000000, 1))),
+        vec![(2, 2), (1, 2), (0, 2), (0, 1), (0, 0), (1, 0), (2, 0), (2, 1)]
+    );
+    assert_eq!(
+        positions(starfield.vaporization_order((1, 1)).rotating(Rotation::CounterClockwise).starting_at((-2000000000, -2000000000)))[0],
+        (0, 0)
+    );
+
+    let starfield = Starfield::from_string(&"##.##".to_string());
+    let order: Vec<(usize, usize, usize)> = starfield.vaporization_order((2, 0))
+        .map(|x| (x.asteroid.0, x.asteroid.1, x.rotation))
+        .collect();
+    assert_eq!(order, vec![(3, 0, 1), (1, 0, 1), (4, 0, 2), (0, 0, 2)]);
+}
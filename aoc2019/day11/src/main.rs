@@ -1,12 +1,21 @@
+extern crate common;
 use std::io;
 use std::num;
 use std::fmt;
-use std::cmp;
 use std::fs::File;
 use std::io::Read;
+use std::fs;
 use std::collections::HashMap;
 
 
+use common::args::Args;
+use common::grid::{Grid, Position, YAxis};
+use common::intcode::IoHandler;
+use common::robot::{Controller, Direction, Robot, RobotIoHandler, Tile, Turn};
+use common::export::{self, Palette, Raster, Rgb};
+use common::ocr;
+
+
 #[derive(Debug)]
 enum ParseError {
     IoError(io::Error),
@@ -86,6 +95,10 @@ impl Program {
         while self.is_running() {
             let opcode = OpCode::read(self);
             result = opcode.execute(self).or(result);
+
+            if self.io_handler.as_ref().is_some_and(|io_handler| io_handler.wants_pause()) {
+                self.pause();
+            }
         }
 
         if self.is_done() { RunResult::Done(result) } else { RunResult::Paused(result) }
@@ -311,14 +324,6 @@ impl ParamMode {
 }
 
 
-trait IoHandler {
-    fn input(&mut self) -> i64;
-    fn output(&mut self, value: i64);
-
-    fn done(&mut self) {
-    }
-}
-
 struct StdInOutIoHandler {
 }
 
@@ -363,164 +368,344 @@ impl FixedIoHandler {
 }
 
 
-#[derive(Debug)]
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Black,
+    White,
+    // colors of variants which paint with more than black and white
+    Other(i64)
 }
 
-impl Direction {
-    fn turn_left(&self) -> Self {
-        match self {
-            Direction::Left => Direction::Down,
-            Direction::Right => Direction::Up,
-            Direction::Up => Direction::Left,
-            Direction::Down => Direction::Right
+// drawn for the colors of variants
+const GRAY: Rgb = (128, 128, 128);
+
+impl Tile for Color {
+    fn from_number(num: i64) -> Option<Self> {
+        match num {
+            0 => Some(Color::Black),
+            1 => Some(Color::White),
+            _ => None
         }
     }
 
-    fn turn_right(&self) -> Self {
+    fn to_number(&self) -> i64 {
         match self {
-            Direction::Left => Direction::Up,
-            Direction::Right => Direction::Down,
-            Direction::Up => Direction::Right,
-            Direction::Down => Direction::Left
+            Color::Black => 0,
+            Color::White => 1,
+            Color::Other(num) => *num
         }
     }
 }
 
-#[derive(Debug)]
-enum Color {
-    Black,
-    White
+
+// how the robot starts and how it understands the instructions of the program
+#[derive(Debug, Clone)]
+struct Rules {
+    start_color: Color,
+    start_direction: Direction,
+    // color codes accepted besides black and white
+    colors: Vec<i64>,
+    turns: Vec<(i64, Turn)>
 }
 
-impl Color {
-    fn from_number(num: i32) -> Self {
-        match num {
-            0 => Color::Black,
-            1 => Color::White,
-            _ => panic!("invalid color {}", num)
+impl Rules {
+    fn new(start_color: Color) -> Self {
+        Rules {
+            start_color,
+            start_direction: Direction::North,
+            colors: Vec::new(),
+            turns: vec![(0, Turn::Left), (1, Turn::Right)]
         }
     }
 
-    fn to_number(&self) -> i32 {
-        match self {
-            Color::Black => 0,
-            Color::White => 1
-        }
+    fn with_color(mut self, code: i64) -> Self {
+        self.colors.push(code);
+        self
+    }
+
+    // replaces the meaning of an existing turn code
+    fn with_turn(mut self, code: i64, turn: Turn) -> Self {
+        self.turns.retain(|(c, _)| *c != code);
+        self.turns.push((code, turn));
+        self
+    }
+
+    fn color(&self, code: i64) -> Option<Color> {
+        Color::from_number(code).or_else(|| {
+            if self.colors.contains(&code) { Some(Color::Other(code)) } else { None }
+        })
+    }
+
+    fn turn(&self, code: i64) -> Option<Turn> {
+        self.turns.iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, turn)| *turn)
     }
 }
 
 
-#[derive(Debug)]
-enum State {
-    ExpectColor,
-    ExpectDirection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stats {
+    // panels painted at least once
+    painted: usize,
+    // paints onto panels which were painted before
+    repaints: usize,
+    visited: usize,
+    moves: usize
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "Panels painted: {}, repaints: {}, panels visited: {}, moves: {}",
+            self.painted, self.repaints, self.visited, self.moves
+        )
+    }
+}
+
+
+#[derive(Debug, Default)]
+struct Reports {
+    // read the registration identifier painted onto the hull
+    identifier: bool,
+    // where to save an image of the painted hull
+    image: Option<String>,
+    stats: bool,
+    heat_map: bool,
+    // where to save the path of the robot, one `x,y` per line
+    path: Option<String>
 }
 
 #[derive(Debug)]
-struct PaintingIoHandler {
-    direction: Direction,
-    position: (i32, i32),
-    tiles: HashMap<(i32, i32), Color>,
-    state: State
+struct Painter {
+    rules: Rules,
+    reports: Reports,
+    // how often every panel was painted and visited
+    paints: Grid<usize>,
+    visits: Grid<usize>,
+    path: Vec<Position>
 }
 
-impl IoHandler for PaintingIoHandler {
-    fn input(&mut self) -> i64 {
-        self.tiles.get(&self.position).unwrap_or(&Color::Black).to_number() as i64
+impl Painter {
+    fn new(rules: Rules, reports: Reports, start: Position) -> Self {
+        let mut visits = Grid::new();
+        visits.insert(start, 1);
+
+        Painter {
+            rules,
+            reports,
+            paints: Grid::new(),
+            visits,
+            path: vec![start]
+        }
     }
 
-    fn output(&mut self, value: i64) {
-        self.state = match self.state {
-            State::ExpectColor => {
-                let color = Color::from_number(value as i32);
-                self.tiles.insert(self.position, color);
-
-                State::ExpectDirection
-            },
-            State::ExpectDirection => {
-                self.direction = match value {
-                    0 => self.direction.turn_left(),
-                    1 => self.direction.turn_right(),
-                    _ => panic!("invalid direction {}", value)
-                };
-
-                self.position = match self.direction {
-                    Direction::Left => (self.position.0 - 1, self.position.1),
-                    Direction::Right => (self.position.0 + 1, self.position.1),
-                    Direction::Up => (self.position.0, self.position.1 + 1),
-                    Direction::Down => (self.position.0, self.position.1 - 1)
-                };
-
-                State::ExpectColor
-            }
-        };
+    fn stats(&self) -> Stats {
+        Stats {
+            painted: self.paints.len(),
+            repaints: self.paints.iter().map(|(_, count)| count - 1).sum(),
+            visited: self.visits.len(),
+            moves: self.path.len() - 1
+        }
+    }
+
+    // visits per panel, north up, `+` for ten visits or more
+    fn heat_map(&self) -> String {
+        self.visits.renderer().y_axis(YAxis::Up).render(|count| match count {
+            None => ' ',
+            Some(&count) if count < 10 => std::char::from_digit(count as u32, 10).unwrap(),
+            Some(_) => '+'
+        })
     }
 
-    fn done(&mut self) {
-        println!("Tiles colored: {}", self.tiles.len());
-        println!("{}\n", self);
+    fn save_path(&self, path: &str) -> io::Result<()> {
+        let lines: Vec<String> = self.path.iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect();
+        fs::write(path, lines.join("\n") + "\n")
     }
 }
 
+impl Controller<Color> for Painter {
+    fn arity(&self) -> usize {
+        2
+    }
 
-impl fmt::Display for PaintingIoHandler {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut min = (0, 0);
-        let mut max = (0, 0);
-        for position in self.tiles.keys() {
-            min = (cmp::min(position.0, min.0), cmp::min(position.1, min.1));
-            max = (cmp::max(position.0, max.0), cmp::max(position.1, max.1));
+    fn input(&mut self, robot: &mut Robot<Color>) -> i64 {
+        robot.current().unwrap_or(&Color::Black).to_number()
+    }
+
+    fn output(&mut self, robot: &mut Robot<Color>, values: &[i64]) {
+        let color = self.rules.color(values[0])
+            .unwrap_or_else(|| panic!("invalid color {}", values[0]));
+        robot.paint(color);
+        *self.paints.get_or_insert_with(robot.position, || 0) += 1;
+
+        robot.turn(self.rules.turn(values[1])
+            .unwrap_or_else(|| panic!("invalid direction {}", values[1])));
+        let position = robot.forward();
+        *self.visits.get_or_insert_with(position, || 0) += 1;
+        self.path.push(position);
+    }
+
+    fn done(&mut self, robot: &Robot<Color>) {
+        println!("Tiles colored: {}", robot.tiles.len());
+        println!("{}\n", render_hull(&robot.tiles));
+
+        if self.reports.stats {
+            println!("{}", self.stats());
         }
-        let width = max.0 - min.0;
-        let height = max.1 - min.1 + 1;
-
-        let mut rows = Vec::new();
-        for y in 0..height {
-            let mut row = Vec::new();
-            for x in 0..width {
-                let position = (min.0 + x, min.1 + y);
-                let value = match self.tiles.get(&position).unwrap_or(&Color::Black) {
-                    Color::White => "\u{2588}",
-                    Color::Black => " "
-                };
-                row.push(value);
+
+        if self.reports.heat_map {
+            println!("{}\n", self.heat_map());
+        }
+
+        if self.reports.identifier {
+            // the robot moves with y growing upwards, text is read top to bottom
+            let hull = robot.tiles.clone().flip_vertical();
+            match ocr::recognize(&hull, |color| *color == Color::White) {
+                Ok(text) => println!("Registration identifier: {}", text),
+                Err(e) => println!("unable to read registration identifier: {}", e)
             }
-            rows.push(row.join(""));
         }
 
-        // we begin with 0 height not max height, so need to flip here
-        rows.reverse();
+        if let Some(ref path) = self.reports.image {
+            let palette = self.rules.colors.iter()
+                .fold(Palette::new(export::BLACK), |palette, code| palette.with(Color::Other(*code), GRAY))
+                .with(Color::White, export::WHITE);
+            // the robot moves with y growing upwards, images grow downwards
+            let hull = robot.tiles.clone().flip_vertical();
+            Raster::from_grid(&hull, &palette, 10).save(path).unwrap();
+            println!("saved hull to {}", path);
+        }
 
-        write!(f, "{}", rows.join("\n"))
+        if let Some(ref path) = self.reports.path {
+            self.save_path(path).unwrap();
+            println!("saved path to {}", path);
+        }
     }
 }
 
-impl PaintingIoHandler {
-    fn new(color: Color) -> Self {
-        let position = (0, 0);
+type PaintingIoHandler = RobotIoHandler<Color, Painter>;
 
-        let mut tiles = HashMap::new();
-        tiles.insert(position, color);
+fn painting_io_handler(rules: Rules, reports: Reports) -> PaintingIoHandler {
+    let mut robot = Robot::new((0, 0), rules.start_direction);
+    robot.paint(rules.start_color);
 
-        PaintingIoHandler {
-            direction: Direction::Up,
-            position: position,
-            tiles: tiles,
-            state: State::ExpectColor
-        }
+    let painter = Painter::new(rules, reports, robot.position);
+    RobotIoHandler::new(robot, painter)
+}
+
+
+fn render_hull(tiles: &Grid<Color>) -> String {
+    tiles.renderer().y_axis(YAxis::Up).render(|color| match color.unwrap_or(&Color::Black) {
+        Color::White => '\u{2588}',
+        Color::Black => ' ',
+        Color::Other(_) => '\u{2592}'
+    })
+}
+
+
+fn parse_direction(name: &str) -> Option<Direction> {
+    match name {
+        "north" | "up" => Some(Direction::North),
+        "east" | "right" => Some(Direction::East),
+        "south" | "down" => Some(Direction::South),
+        "west" | "left" => Some(Direction::West),
+        _ => None
+    }
+}
+
+fn parse_turn(name: &str) -> Option<Turn> {
+    match name {
+        "left" => Some(Turn::Left),
+        "right" => Some(Turn::Right),
+        "around" => Some(Turn::Around),
+        "straight" => Some(Turn::Straight),
+        _ => None
     }
 }
 
 
 fn main() {
-    let mut program = Program::from_file(&mut File::open("../input.txt").unwrap()).unwrap();
-    program.set_io_handler(Box::new(PaintingIoHandler::new(Color::Black)));
+    let program = Program::from_file(&mut File::open("../input.txt").unwrap()).unwrap();
+
+    // day11 [IMAGE] [--start-color N] [--direction north|east|south|west] [--color N]...
+    //       [--turn CODE=left|right|around|straight]... [--stats] [--heatmap] [--path FILE]
+    let args = Args::from_env();
+    let image = args.positional().map(|x| x.to_string());
+
+    let reports = || Reports {
+        stats: args.flag("--stats"),
+        heat_map: args.flag("--heatmap"),
+        ..Reports::default()
+    };
+
+    let variant = ["--start-color", "--direction", "--color", "--turn"].iter().any(|name| args.flag(name));
+    if variant {
+        let mut rules = args.options("--color").into_iter()
+            .map(|code| code.parse::<i64>().expect("invalid color"))
+            .fold(Rules::new(Color::Black), |rules, code| rules.with_color(code));
+        rules = args.options("--turn").into_iter()
+            .map(|turn| {
+                let mut parts = turn.splitn(2, '=');
+                let code = parts.next().and_then(|x| x.parse::<i64>().ok()).expect("invalid turn code");
+                (code, parts.next().and_then(parse_turn).expect("invalid turn"))
+            })
+            .fold(rules, |rules, (code, turn)| rules.with_turn(code, turn));
+        if let Some(color) = args.option("--start-color") {
+            let code = color.parse::<i64>().expect("invalid color");
+            rules.start_color = rules.color(code).expect("unknown start color");
+        }
+        if let Some(direction) = args.option("--direction") {
+            rules.start_direction = parse_direction(direction).expect("invalid direction");
+        }
 
-    program.run();
+        let mut painting = program.clone();
+        painting.set_io_handler(Box::new(painting_io_handler(
+            rules, Reports { image, path: args.option("--path").map(|x| x.to_string()), ..reports() }
+        )));
+        painting.run();
+        return;
+    }
+
+    let mut painting = program.clone();
+    painting.set_io_handler(Box::new(painting_io_handler(Rules::new(Color::Black), reports())));
+    painting.run();
+
+    let mut registration = program.clone();
+    registration.set_io_handler(Box::new(painting_io_handler(
+        Rules::new(Color::White),
+        Reports { identifier: true, image, path: args.option("--path").map(|x| x.to_string()), ..reports() }
+    )));
+    registration.run();
 }
 
+
+#[test]
+fn test_painting_rules() {
+    let rules = Rules::new(Color::White)
+        .with_color(5)
+        .with_turn(1, Turn::Around)
+        .with_turn(2, Turn::Straight);
+    assert_eq!(rules.color(1), Some(Color::White));
+    assert_eq!(rules.color(5), Some(Color::Other(5)));
+    assert_eq!(rules.color(3), None);
+    assert_eq!(rules.turn(0), Some(Turn::Left));
+    assert_eq!(rules.turn(1), Some(Turn::Around));
+    assert_eq!(rules.turn(2), Some(Turn::Straight));
+
+    let mut handler = painting_io_handler(rules, Reports::default());
+    assert_eq!(handler.input(), 1);
+    // paint, turn around and come back to the start
+    for &value in &[5, 1, 0, 1] {
+        handler.output(value);
+    }
+    assert_eq!(handler.input(), 5);
+    assert_eq!(handler.robot().position, (0, 0));
+
+    let painter = handler.controller();
+    assert_eq!(painter.stats(), Stats { painted: 2, repaints: 0, visited: 2, moves: 2 });
+    assert_eq!(painter.path, vec![(0, 0), (0, -1), (0, 0)]);
+    assert_eq!(painter.heat_map(), "2\n1");
+}
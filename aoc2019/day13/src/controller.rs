@@ -0,0 +1,307 @@
+use std::io;
+use std::fs;
+use std::thread;
+use std::rc::Rc;
+use std::path::Path;
+use std::cell::Cell;
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use termion;
+use termion::AsyncReader;
+use termion::event::Key;
+use termion::input::{Keys, TermRead};
+use termion::raw::{IntoRawMode, RawTerminal};
+
+use game::Game;
+use screen::Screen;
+use solver;
+use Program;
+
+
+// decides the joystick position for every frame: -1 left, 0 neutral, 1 right
+pub trait Controller {
+    fn input(&mut self, game: &Game, program: &Program) -> i64;
+
+    fn done(&mut self, _game: &Game) {
+    }
+}
+
+
+// move the paddle one step towards `target`
+pub fn steer(paddle: i32, target: i32) -> i64 {
+    (target - paddle).signum() as i64
+}
+
+
+// joystick on the arrow keys, `p` pauses and `s` saves the inputs so far to resume later
+pub struct Keyboard {
+    screen: Screen,
+    stdout: RawTerminal<io::Stdout>,
+    keys: Keys<AsyncReader>,
+    save: String,
+    paused: bool,
+    // inputs of a saved game, replayed without rendering before taking over
+    resume: Replay
+}
+
+impl Keyboard {
+    pub fn new(frame_rate: Option<NonZeroU32>, save: String, resume: Vec<i64>) -> Self {
+        Keyboard {
+            screen: Screen::new(frame_rate),
+            stdout: io::stdout().into_raw_mode().unwrap(),
+            keys: termion::async_stdin().keys(),
+            save,
+            paused: false,
+            resume: Replay::new(resume)
+        }
+    }
+
+    // without frame rate the game waits for a direction, otherwise it keeps going
+    fn read_direction(&mut self, game: &Game) -> i64 {
+        let mut direction = None;
+        loop {
+            while let Some(Ok(key)) = self.keys.next() {
+                match key {
+                    Key::Left => direction = Some(-1),
+                    Key::Right => direction = Some(1),
+                    Key::Up | Key::Down => direction = Some(0),
+                    Key::Char('p') => {
+                        self.paused = !self.paused;
+                        self.screen.set_message(if self.paused { Some("paused") } else { None });
+                        self.screen.draw_status(&mut self.stdout, game).unwrap();
+                    },
+                    Key::Char('s') => {
+                        let message = match Replay::save(&game.inputs, &self.save) {
+                            Ok(_) => format!("saved to {}", self.save),
+                            Err(e) => format!("unable to save: {}", e)
+                        };
+                        self.screen.set_message(Some(message));
+                        self.screen.draw_status(&mut self.stdout, game).unwrap();
+                    },
+                    _ => ()
+                }
+            }
+
+            if !self.paused {
+                if self.screen.has_frame_rate() {
+                    if self.screen.frame_due() {
+                        break direction.unwrap_or(0);
+                    }
+                } else if let Some(direction) = direction {
+                    break direction;
+                }
+            }
+
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+impl Controller for Keyboard {
+    fn input(&mut self, game: &Game, program: &Program) -> i64 {
+        if !self.resume.is_done() {
+            return self.resume.input(game, program);
+        }
+
+        self.screen.draw(&mut self.stdout, game).unwrap();
+        self.read_direction(game)
+    }
+
+    fn done(&mut self, game: &Game) {
+        self.screen.draw(&mut self.stdout, game).unwrap();
+        self.screen.close(&mut self.stdout).unwrap();
+    }
+}
+
+
+// renders the game while another controller plays it
+pub struct Watch {
+    screen: Screen,
+    stdout: io::Stdout,
+    inner: Box<dyn Controller>
+}
+
+impl Watch {
+    pub fn new(frame_rate: Option<NonZeroU32>, inner: Box<dyn Controller>) -> Self {
+        Watch {
+            screen: Screen::new(frame_rate),
+            stdout: io::stdout(),
+            inner
+        }
+    }
+}
+
+impl Controller for Watch {
+    fn input(&mut self, game: &Game, program: &Program) -> i64 {
+        self.screen.draw(&mut self.stdout, game).unwrap();
+        self.screen.wait_frame();
+        self.inner.input(game, program)
+    }
+
+    fn done(&mut self, game: &Game) {
+        self.inner.done(game);
+        self.screen.draw(&mut self.stdout, game).unwrap();
+        self.screen.close(&mut self.stdout).unwrap();
+    }
+}
+
+
+// follows the ball with the paddle
+pub struct Tracking;
+
+impl Controller for Tracking {
+    fn input(&mut self, game: &Game, _program: &Program) -> i64 {
+        let paddle = game.paddle().expect("no paddle");
+        let ball = game.ball().expect("no ball");
+
+        steer(paddle.0, ball.0)
+    }
+}
+
+
+// moves the paddle to where the ball is going to land, by running ahead on a snapshot
+pub struct Predictive {
+    target: Option<i32>,
+    // keeps the landing until the ball bounces instead of simulating again on every input
+    caching: bool,
+    // instructions executed by all simulations, shared to be read once the game is over
+    simulated: Rc<Cell<u64>>
+}
+
+impl Predictive {
+    pub fn new() -> Self {
+        Predictive { target: None, caching: true, simulated: Rc::new(Cell::new(0)) }
+    }
+
+    pub fn caching(mut self, caching: bool) -> Self {
+        self.caching = caching;
+        self
+    }
+
+    pub fn simulated(&self) -> Rc<Cell<u64>> {
+        self.simulated.clone()
+    }
+}
+
+impl Controller for Predictive {
+    fn input(&mut self, game: &Game, program: &Program) -> i64 {
+        let paddle = game.paddle().expect("no paddle");
+        let ball = game.ball().expect("no ball");
+
+        // the ball is about to bounce off the paddle, afterwards it has a new target
+        if ball.1 == paddle.1 - 1 {
+            self.target = None;
+            return steer(paddle.0, ball.0);
+        }
+
+        if self.target.is_none() || !self.caching {
+            let (landing, executed) = solver::predict_landing(program, paddle.1 - 1);
+            self.simulated.set(self.simulated.get() + executed);
+            self.target = landing;
+        }
+
+        steer(paddle.0, self.target.unwrap_or(ball.0))
+    }
+}
+
+// plays back recorded inputs, stays neutral once the recording is exhausted
+pub struct Replay {
+    inputs: Vec<i64>,
+    position: usize
+}
+
+impl Replay {
+    pub fn new(inputs: Vec<i64>) -> Self {
+        Replay { inputs, position: 0 }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Replay::load(path).map(Replay::new)
+    }
+
+    // inputs separated by commas or whitespace
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<i64>> {
+        let data = fs::read_to_string(path)?;
+        data.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse::<i64>().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+            .collect()
+    }
+
+    pub fn save<P: AsRef<Path>>(inputs: &[i64], path: P) -> io::Result<()> {
+        let data: Vec<String> = inputs.iter().map(|x| x.to_string()).collect();
+        fs::write(path, data.join(","))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.position >= self.inputs.len()
+    }
+}
+
+impl Controller for Replay {
+    fn input(&mut self, _game: &Game, _program: &Program) -> i64 {
+        let value = self.inputs.get(self.position).cloned().unwrap_or(0);
+        self.position += 1;
+        value
+    }
+}
+
+
+#[cfg(test)]
+fn test_game(outputs: &[i64]) -> Game {
+    let mut game = Game::new();
+    for &value in outputs {
+        game.output(value);
+    }
+    game
+}
+
+#[test]
+fn test_steer() {
+    assert_eq!(steer(3, 7), 1);
+    assert_eq!(steer(7, 3), -1);
+    assert_eq!(steer(5, 5), 0);
+
+    let program = Program::from_opcodes(vec![99]);
+    let mut tracking = Tracking;
+    // ball left of the paddle, then right of it, then right above it
+    assert_eq!(tracking.input(&test_game(&[1, 3, 4, 4, 5, 3]), &program), -1);
+    assert_eq!(tracking.input(&test_game(&[6, 3, 4, 4, 5, 3]), &program), 1);
+    assert_eq!(tracking.input(&test_game(&[4, 4, 4, 4, 5, 3]), &program), 0);
+}
+
+#[test]
+fn test_replay() {
+    let program = Program::from_opcodes(vec![99]);
+    let game = Game::new();
+
+    let mut replay = Replay::new(vec![1, -1, 0, 1]);
+    let inputs: Vec<i64> = (0..4).map(|_| replay.input(&game, &program)).collect();
+    assert_eq!(inputs, vec![1, -1, 0, 1]);
+    assert!(replay.is_done());
+    // neutral once the recording is exhausted
+    assert_eq!(replay.input(&game, &program), 0);
+    assert_eq!(replay.input(&game, &program), 0);
+
+    assert!(Replay::new(Vec::new()).is_done());
+}
+
+#[test]
+fn test_replay_files() {
+    let path = std::env::temp_dir().join(format!("day13-replay-{}.txt", std::process::id()));
+
+    Replay::save(&[1, 0, -1, -1], &path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "1,0,-1,-1");
+    assert_eq!(Replay::load(&path).unwrap(), vec![1, 0, -1, -1]);
+
+    // commas and whitespace both separate inputs
+    fs::write(&path, "1, 0\n-1\t1\n\n").unwrap();
+    assert_eq!(Replay::load(&path).unwrap(), vec![1, 0, -1, 1]);
+
+    fs::write(&path, "1,left").unwrap();
+    assert_eq!(Replay::load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    fs::remove_file(&path).unwrap();
+    assert_eq!(Replay::load(&path).unwrap_err().kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use common::grid::{Grid, Position};
+use controller::Controller;
+use {IoHandler, Program};
+
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball
+}
+
+impl Tile {
+    pub fn from_number(num: i32) -> Option<Self> {
+        match num {
+            0 => Some(Tile::Empty),
+            1 => Some(Tile::Wall),
+            2 => Some(Tile::Block),
+            3 => Some(Tile::Paddle),
+            4 => Some(Tile::Ball),
+            _ => None
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Empty=> ' ',
+            Tile::Wall => '\u{2588}',
+            Tile::Block => 'X',
+            Tile::Paddle => '-',
+            Tile::Ball => 'O'
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+enum State {
+    ExpectX,
+    ExpectY(i32),
+    ExpectType(i32, i32)
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub tiles: Grid<Tile>,
+    pub score: i64,
+    // every joystick input, one per frame
+    pub inputs: Vec<i64>,
+    state: State
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game {
+            tiles: Grid::new(),
+            score: 0,
+            inputs: Vec::new(),
+            state: State::ExpectX
+        }
+    }
+
+    pub fn output(&mut self, value: i64) {
+        self.state = match self.state {
+            State::ExpectX => State::ExpectY(value as i32),
+            State::ExpectY(x) => State::ExpectType(x, value as i32),
+            State::ExpectType(x, y) => {
+                // the score is sent to (-1, 0), scores up to 4 look like tiles
+                if (x, y) == (-1, 0) {
+                    self.score = value;
+                } else if let Some(tile) = Tile::from_number(value as i32) {
+                    self.tiles.insert((x, y), tile);
+                }
+                State::ExpectX
+            }
+        };
+    }
+
+    pub fn tiles<'a>(&'a self, tile_type: Tile) -> impl Iterator<Item=Position> + 'a {
+        self.tiles.positions_of(tile_type)
+    }
+
+    pub fn ball(&self) -> Option<Position> {
+        self.tiles(Tile::Ball).next()
+    }
+
+    pub fn paddle(&self) -> Option<Position> {
+        self.tiles(Tile::Paddle).next()
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.tiles(Tile::Block).count()
+    }
+
+    pub fn frames(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            score: self.score,
+            frames: self.frames(),
+            blocks: self.blocks()
+        }
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let screen = self.tiles.renderer()
+            .line_ending("\r\n")
+            .render(|tile| tile.unwrap_or(&Tile::Empty).to_char());
+        f.write_str(&screen)
+    }
+}
+
+
+// outcome of a game, displayed as a single line of JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    pub score: i64,
+    pub frames: usize,
+    pub blocks: usize
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "{{\"score\": {}, \"frames\": {}, \"blocks\": {}}}",
+            self.score, self.frames, self.blocks
+        )
+    }
+}
+
+
+struct GameIoHandler {
+    game: Rc<RefCell<Game>>,
+    controller: Box<dyn Controller>
+}
+
+impl IoHandler for GameIoHandler {
+    fn input(&mut self, program: &Program) -> i64 {
+        let value = self.controller.input(&self.game.borrow(), program);
+        self.game.borrow_mut().inputs.push(value);
+        value
+    }
+
+    fn output(&mut self, value: i64) {
+        self.game.borrow_mut().output(value);
+    }
+
+    fn done(&mut self) {
+        self.controller.done(&self.game.borrow());
+    }
+}
+
+
+// runs the game until the program exits and returns the final state
+pub fn play(program: &mut Program, controller: Box<dyn Controller>) -> Game {
+    let game = Rc::new(RefCell::new(Game::new()));
+
+    program.set_io_handler(Box::new(GameIoHandler {
+        game: game.clone(),
+        controller
+    }));
+    program.run();
+
+    let result = game.borrow().clone();
+    result
+}
+
+
+#[test]
+fn test_game_output() {
+    let mut game = Game::new();
+    for &value in &[0, 0, 1, 2, 0, 1, 2, 1, 4, -1, 0, 3] {
+        game.output(value);
+    }
+
+    assert_eq!(game.score, 3);
+    assert_eq!(game.ball(), Some((2, 1)));
+    assert_eq!(game.to_string(), "\u{2588} \u{2588}\r\n  O");
+}
+
+#[test]
+fn test_report() {
+    let report = Report { score: 15410, frames: 6123, blocks: 0 };
+    assert_eq!(report.to_string(), "{\"score\": 15410, \"frames\": 6123, \"blocks\": 0}");
+}
+
+#[test]
+fn test_play() {
+    use controller::Replay;
+
+    // draws a ball, reads the joystick and sends it back as the score
+    let mut program = Program::from_opcodes(vec![
+        104, 1, 104, 0, 104, 4,
+        3, 100,
+        104, -1, 104, 0, 4, 100,
+        99
+    ]);
+    let game = play(&mut program, Box::new(Replay::new(vec![-1])));
+
+    assert_eq!(game.ball(), Some((1, 0)));
+    assert_eq!(game.inputs, vec![-1]);
+    assert_eq!(game.score, -1);
+    assert_eq!(game.report(), Report { score: -1, frames: 1, blocks: 0 });
+}
@@ -1,16 +1,18 @@
+extern crate common;
 extern crate termion;
 use std::io;
 use std::num;
-use std::fmt;
-use std::cmp;
 use std::fs::File;
 use std::io::Read;
 use std::collections::HashMap;
 
-use termion::event::Key;
-use termion::input::TermRead;
-use termion::raw::IntoRawMode;
-use std::io::Write;
+mod game;
+mod controller;
+mod screen;
+mod solver;
+
+use common::args::Args;
+use controller::{Controller, Keyboard, Predictive, Replay, Tracking, Watch};
 
 #[derive(Debug)]
 enum ParseError {
@@ -36,6 +38,10 @@ impl RunResult {
 
 struct Program {
     position: usize,
+    // start of the instruction currently being executed
+    instruction: usize,
+    // amount of executed instructions
+    executed: u64,
     data: Vec<i64>,
     done: bool,
     paused: bool,
@@ -48,6 +54,8 @@ impl Clone for Program {
     fn clone(&self) -> Self {
         Program {
             position: self.position,
+            instruction: self.instruction,
+            executed: self.executed,
             data: self.data.clone(),
             done: self.done,
             paused: self.paused,
@@ -73,6 +81,8 @@ impl Program {
     pub fn from_opcodes(opcodes: Vec<i64>) -> Self {
         Program {
             position: 0,
+            instruction: 0,
+            executed: 0,
             data: opcodes,
             done: false,
             paused: false,
@@ -91,6 +101,11 @@ impl Program {
         while self.is_running() {
             let opcode = OpCode::read(self);
             result = opcode.execute(self).or(result);
+            self.executed += 1;
+
+            if self.io_handler.as_ref().is_some_and(|io_handler| io_handler.wants_pause()) {
+                self.pause();
+            }
         }
 
         if self.is_done() { RunResult::Done(result) } else { RunResult::Paused(result) }
@@ -155,6 +170,10 @@ impl Program {
         self.paused = false;
     }
 
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
     pub fn is_done(&self) -> bool {
         self.done
     }
@@ -169,7 +188,18 @@ impl Program {
     }
 
     pub fn input(&mut self) -> i64 {
-        self.io_handler.as_mut().expect("expected io handler").input()
+        // the handler gets to look at the program, e.g. to simulate ahead on a snapshot
+        let mut io_handler = self.io_handler.take().expect("expected io handler");
+        let value = io_handler.input(self);
+        self.io_handler = Some(io_handler);
+        value
+    }
+
+    // copy of the program without io handler, rewound to the start of the current instruction
+    pub fn snapshot(&self) -> Self {
+        let mut snapshot = self.clone();
+        snapshot.position = self.instruction;
+        snapshot
     }
 }
 
@@ -190,6 +220,7 @@ enum OpCode {
 
 impl OpCode {
     fn read(program: &mut Program) -> OpCode {
+        program.instruction = program.position;
         let instruction = program.read(ParamMode::Immediate);
         let opcode = instruction % 100;
         let param_mode = instruction / 100;
@@ -317,18 +348,23 @@ impl ParamMode {
 
 
 trait IoHandler {
-    fn input(&mut self) -> i64;
+    fn input(&mut self, program: &Program) -> i64;
     fn output(&mut self, value: i64);
 
     fn done(&mut self) {
     }
+
+    // checked after every instruction, pauses the program when true
+    fn wants_pause(&self) -> bool {
+        false
+    }
 }
 
 struct StdInOutIoHandler {
 }
 
 impl IoHandler for StdInOutIoHandler {
-    fn input(&mut self) -> i64 {
+    fn input(&mut self, _program: &Program) -> i64 {
         let mut input = String::new();
         io::stdin().read_line(&mut input).unwrap();
         return input.trim().parse().unwrap();
@@ -352,7 +388,7 @@ struct FixedIoHandler {
 }
 
 impl IoHandler for FixedIoHandler {
-    fn input(&mut self) -> i64 {
+    fn input(&mut self, _program: &Program) -> i64 {
         self.input.remove(0)
     }
 
@@ -369,159 +405,55 @@ impl FixedIoHandler {
 
 
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Tile {
-    Empty,
-    Wall,
-    Block,
-    Paddle,
-    Ball
-}
-
-impl Tile {
-    fn from_number(num: i32) -> Option<Self> {
-        match num {
-            0 => Some(Tile::Empty),
-            1 => Some(Tile::Wall),
-            2 => Some(Tile::Block),
-            3 => Some(Tile::Paddle),
-            4 => Some(Tile::Ball),
-            _ => None
-        }
-    }
-}
-
-
-#[derive(Debug)]
-enum State {
-    ExpectX,
-    ExpectY(i32),
-    ExpectType(i32, i32)
-}
-
-#[derive(Debug)]
-struct GameIoHandler {
-    interactive: bool,
-    tiles: HashMap<(i32, i32), Tile>,
-    state: State,
-    score: i64
-}
-
-impl IoHandler for GameIoHandler {
-    fn input(&mut self) -> i64 {
-        if !self.interactive {
-            let paddle = self.tiles(Tile::Paddle).nth(0).unwrap();
-            let ball = self.tiles(Tile::Ball).nth(0).unwrap();
-
-            return match paddle.0 - ball.0 { 0 => 0, diff => -diff.signum() as i64 };
-        }
-
-        let mut stdout = io::stdout().into_raw_mode().unwrap();
-        write!(
-            stdout,
-            "{}{}Score:{}\r\n{}{}",
-            termion::clear::All,
-            termion::cursor::Goto(1, 1),
-            self.score,
-            self,
-            termion::cursor::Hide
-        ).unwrap();
-        stdout.flush().unwrap();
-
-        let mut stdin = io::stdin().keys();
-        return loop {
-            match stdin.next() {
-                Some(Ok(Key::Left)) => break -1,
-                Some(Ok(Key::Right)) => break 1,
-                Some(Ok(Key::Up)) => break 0,
-                Some(Ok(Key::Down)) => break 0,
-                _ => ()
-            }
-        }
-    }
-
-    fn output(&mut self, value: i64) {
-        self.state = match self.state {
-            State::ExpectX => State::ExpectY(value as i32),
-            State::ExpectY(x) => State::ExpectType(x, value as i32),
-            State::ExpectType(x, y) => {
-                if let Some(tile) = Tile::from_number(value as i32) {
-                    self.tiles.insert((x, y), tile);
-                } else {
-                    self.score = value;
-                }
-                State::ExpectX
-            }
-        };
-    }
-
-    fn done(&mut self) {
-        println!("\nBlock tiles: {}", self.tiles(Tile::Block).count());
-        println!("Score: {}", self.score);
-        println!("{}\n", self);
-    }
-}
-
-
-impl fmt::Display for GameIoHandler {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut min = (0, 0);
-        let mut max = (0, 0);
-        for position in self.tiles.keys() {
-            min = (cmp::min(position.0, min.0), cmp::min(position.1, min.1));
-            max = (cmp::max(position.0, max.0), cmp::max(position.1, max.1));
-        }
-        let width = max.0 - min.0;
-        let height = max.1 - min.1 + 1;
-
-        let mut rows = Vec::new();
-        for y in 0..height {
-            let mut row = Vec::new();
-            for x in 0..width+1 {
-                let position = (min.0 + x, min.1 + y);
-                let value = match self.tiles.get(&position).unwrap_or(&Tile::Empty) {
-                    Tile::Empty=> " ",
-                    Tile::Wall => "\u{2588}",
-                    Tile::Block => "X",
-                    Tile::Paddle => "-",
-                    Tile::Ball => "O"
-                };
-                row.push(value);
-            }
-            rows.push(row.join(""));
-        }
-
-        write!(f, "{}", rows.join("\r\n"))
-    }
-}
-
-impl GameIoHandler {
-    fn new() -> Self {
-        GameIoHandler {
-            interactive: false,
-            tiles: HashMap::new(),
-            state: State::ExpectX,
-            score: 0
-        }
-    }
-
-    fn tiles<'a>(&'a self, tile_type: Tile) -> impl Iterator<Item=&(i32, i32)> + 'a {
-        self.tiles.iter()
-            .filter(move |(_, v)| &tile_type == *v)
-            .map(|(k, _)| k)
-    }
-}
-
-
 fn main() {
     let mut program = Program::from_file(&mut File::open("../input.txt").unwrap()).unwrap();
-
-    let mut handler = GameIoHandler::new();
-    handler.interactive = false;
-    program.set_io_handler(Box::new(handler));
-
+    // insert coins
     program.write(0, 2);
 
-    program.run();
+    // day13 [interactive|track|predict|solve|replay FILE] [--fps N] [--watch] [--record FILE]
+    //       [--save FILE] [--resume FILE]
+    let args = Args::from_env();
+    let frame_rate = args.frame_rate();
+
+    if args.get(0) == Some("solve") {
+        println!("{}", solver::solve(program));
+        return;
+    }
+
+    // instructions simulated by the predictions, reported like the solver does
+    let mut simulated = None;
+    let mut controller: Box<dyn Controller> = match args.get(0) {
+        Some("interactive") => {
+            let save = args.option("--save").map(|x| x.to_string()).unwrap_or_else(|| "day13.save".to_string());
+            let resume = args.option("--resume")
+                .map(|path| Replay::load(path).unwrap())
+                .unwrap_or_default();
+            Box::new(Keyboard::new(frame_rate, save, resume))
+        },
+        Some("predict") => {
+            let predictive = Predictive::new();
+            simulated = Some(predictive.simulated());
+            Box::new(predictive)
+        },
+        Some("replay") => {
+            let path = args.get(1).expect("replay needs a file");
+            Box::new(Replay::from_file(path).unwrap())
+        },
+        Some("track") | None => Box::new(Tracking),
+        Some(mode) => panic!("invalid mode {}", mode)
+    };
+    if args.flag("--watch") {
+        controller = Box::new(Watch::new(frame_rate, controller));
+    }
+
+    let game = game::play(&mut program, controller);
+
+    if let Some(path) = args.option("--record") {
+        Replay::save(&game.inputs, path).unwrap();
+    }
+
+    match simulated {
+        Some(simulated) => println!("{}", solver::Solution::new(&game, &program, simulated.get())),
+        None => println!("{}", game.report())
+    }
 }
-
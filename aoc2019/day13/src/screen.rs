@@ -0,0 +1,185 @@
+use std::io;
+use std::thread;
+use std::io::Write;
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+
+use termion;
+use termion::cursor::Goto;
+
+use common::grid::{Grid, Position};
+use game::{Game, Tile};
+
+
+// terminal renderer which only redraws the tiles that changed since the last frame
+pub struct Screen {
+    frame: Option<Duration>,
+    last_frame: Option<Instant>,
+    previous: Option<Grid<Tile>>,
+    ball: Option<Position>,
+    velocity: Option<Position>,
+    message: Option<String>
+}
+
+impl Screen {
+    // without frame rate, frames are drawn as fast as they are requested
+    pub fn new(frame_rate: Option<NonZeroU32>) -> Self {
+        Screen {
+            frame: frame_rate.map(|fps| Duration::from_secs(1) / fps.get()),
+            last_frame: None,
+            previous: None,
+            ball: None,
+            velocity: None,
+            message: None
+        }
+    }
+
+    pub fn has_frame_rate(&self) -> bool {
+        self.frame.is_some()
+    }
+
+    // shown in the status bar until replaced
+    pub fn set_message<S: Into<String>>(&mut self, message: Option<S>) {
+        self.message = message.map(|message| message.into());
+    }
+
+    pub fn draw<W: Write>(&mut self, out: &mut W, game: &Game) -> io::Result<()> {
+        if self.previous.is_none() {
+            write!(out, "{}{}", termion::clear::All, termion::cursor::Hide)?;
+        }
+
+        for (position, tile) in game.tiles.iter() {
+            let changed = self.previous.as_ref()
+                .map(|previous| previous.get(position) != Some(tile))
+                .unwrap_or(true);
+
+            // the score is sent with a negative x, it never ends up on the screen
+            if changed && position.0 >= 0 && position.1 >= 0 {
+                write!(out, "{}{}", goto(position), tile.to_char())?;
+            }
+        }
+        self.previous = Some(game.tiles.clone());
+
+        let ball = game.ball();
+        if ball != self.ball {
+            self.velocity = match (self.ball, ball) {
+                (Some(a), Some(b)) => Some((b.0 - a.0, b.1 - a.1)),
+                _ => None
+            };
+            self.ball = ball;
+        }
+
+        self.last_frame = Some(Instant::now());
+        self.draw_status(out, game)
+    }
+
+    pub fn draw_status<W: Write>(&mut self, out: &mut W, game: &Game) -> io::Result<()> {
+        let velocity = self.velocity
+            .map(|(x, y)| format!("({:+}, {:+})", x, y))
+            .unwrap_or_else(|| "-".to_string());
+
+        write!(
+            out,
+            "{}{}Score: {}  Blocks: {}  Ball: {}",
+            Goto(1, 1),
+            termion::clear::CurrentLine,
+            game.score,
+            game.blocks(),
+            velocity
+        )?;
+        if let Some(ref message) = self.message {
+            write!(out, "  [{}]", message)?;
+        }
+        out.flush()
+    }
+
+    // true once the current frame has been shown long enough
+    pub fn frame_due(&self) -> bool {
+        match (self.frame, self.last_frame) {
+            (Some(frame), Some(last_frame)) => last_frame.elapsed() >= frame,
+            _ => true
+        }
+    }
+
+    pub fn wait_frame(&self) {
+        if let (Some(frame), Some(last_frame)) = (self.frame, self.last_frame) {
+            let elapsed = last_frame.elapsed();
+            if elapsed < frame {
+                thread::sleep(frame - elapsed);
+            }
+        }
+    }
+
+    // moves the cursor below the board and shows it again
+    pub fn close<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let height = self.previous.as_ref()
+            .and_then(|tiles| tiles.bounds())
+            .map(|bounds| bounds.max.1 + 1)
+            .unwrap_or(0);
+
+        write!(out, "{}{}\r\n", goto((0, height)), termion::cursor::Show)?;
+        out.flush()
+    }
+}
+
+
+// the first line is reserved for the status bar
+fn goto(position: Position) -> Goto {
+    Goto(position.0 as u16 + 1, position.1 as u16 + 2)
+}
+
+
+#[cfg(test)]
+fn test_draw(screen: &mut Screen, game: &Game) -> String {
+    let mut out = Vec::new();
+    screen.draw(&mut out, game).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[cfg(test)]
+fn test_status(blocks: usize, velocity: &str) -> String {
+    format!("{}{}Score: 0  Blocks: {}  Ball: {}", Goto(1, 1), termion::clear::CurrentLine, blocks, velocity)
+}
+
+#[test]
+fn test_screen_redraws_changes() {
+    let mut game = Game::new();
+    // a wall, two blocks and the ball
+    for &value in &[0, 0, 1, 1, 0, 2, 2, 0, 2, 1, 1, 4] {
+        game.output(value);
+    }
+
+    let mut screen = Screen::new(None);
+    let first = test_draw(&mut screen, &game);
+    assert!(first.starts_with(&format!("{}{}", termion::clear::All, termion::cursor::Hide)));
+    for (position, tile) in game.tiles.iter() {
+        assert!(first.contains(&format!("{}{}", goto(position), tile.to_char())));
+    }
+    assert!(first.ends_with(&test_status(2, "-")));
+
+    // nothing changed, only the status bar is written
+    assert_eq!(test_draw(&mut screen, &game), test_status(2, "-"));
+
+    // a block is destroyed
+    for &value in &[2, 0, 0] {
+        game.output(value);
+    }
+    assert_eq!(test_draw(&mut screen, &game), format!("{} {}", goto((2, 0)), test_status(1, "-")));
+
+    // the ball moves down and right, which clears its old tile
+    for &value in &[1, 1, 0, 2, 2, 4] {
+        game.output(value);
+    }
+    let moved = test_draw(&mut screen, &game);
+    assert_eq!(moved.matches("\x1b[").count(), 2 + 2);
+    assert!(moved.contains(&format!("{} ", goto((1, 1)))));
+    assert!(moved.contains(&format!("{}O", goto((2, 2)))));
+    assert!(moved.ends_with(&test_status(1, "(+1, +1)")));
+
+    // the velocity stays until the ball moves again
+    assert_eq!(test_draw(&mut screen, &game), test_status(1, "(+1, +1)"));
+    for &value in &[2, 2, 0, 1, 1, 4] {
+        game.output(value);
+    }
+    assert!(test_draw(&mut screen, &game).ends_with(&test_status(1, "(-1, -1)")));
+}
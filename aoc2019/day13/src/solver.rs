@@ -0,0 +1,184 @@
+use std::fmt;
+use std::rc::Rc;
+use std::cell::Cell;
+#[cfg(test)]
+use std::cell::RefCell;
+
+use game;
+use game::Tile;
+use controller::Predictive;
+#[cfg(test)]
+use controller::Controller;
+use game::Game;
+use {IoHandler, Program};
+
+
+// x coordinate at which the ball next reaches `row`, None if the game ends before,
+// together with the amount of instructions the simulation took
+pub fn predict_landing(program: &Program, row: i32) -> (Option<i32>, u64) {
+    let landing = Rc::new(Cell::new(None));
+
+    let mut simulation = program.snapshot();
+    simulation.set_io_handler(Box::new(BallTracker {
+        row,
+        landing: landing.clone(),
+        values: Vec::with_capacity(3)
+    }));
+    simulation.run();
+
+    (landing.get(), simulation.executed() - program.executed())
+}
+
+// follows the ball on a simulation with the joystick in neutral
+struct BallTracker {
+    row: i32,
+    landing: Rc<Cell<Option<i32>>>,
+    values: Vec<i64>
+}
+
+impl IoHandler for BallTracker {
+    fn input(&mut self, _program: &Program) -> i64 {
+        0
+    }
+
+    fn output(&mut self, value: i64) {
+        self.values.push(value);
+        if self.values.len() < 3 {
+            return;
+        }
+
+        let (x, y, tile) = (self.values[0], self.values[1], self.values[2]);
+        self.values.clear();
+
+        if x >= 0 && y == self.row as i64 && Tile::from_number(tile as i32) == Some(Tile::Ball) {
+            self.landing.set(Some(x as i32));
+        }
+    }
+
+    fn wants_pause(&self) -> bool {
+        self.landing.get().is_some()
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Solution {
+    pub score: i64,
+    pub blocks: usize,
+    pub inputs: usize,
+    // instructions of the game itself and of all simulations
+    pub instructions: u64,
+    pub simulated_instructions: u64
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{{\"score\": {}, \"blocks\": {}, \"inputs\": {}, \"instructions\": {}, \"simulated_instructions\": {}}}",
+            self.score, self.blocks, self.inputs, self.instructions, self.simulated_instructions
+        )
+    }
+}
+
+impl Solution {
+    // the outcome of a finished game on `program`, with the instructions its predictions simulated
+    pub fn new(game: &Game, program: &Program, simulated_instructions: u64) -> Self {
+        Solution {
+            score: game.score,
+            blocks: game.blocks(),
+            inputs: game.inputs.len(),
+            instructions: program.executed(),
+            simulated_instructions
+        }
+    }
+}
+
+// plays the game to the end without rendering anything
+pub fn solve(mut program: Program) -> Solution {
+    // simulates again on every input, the count covers a prediction per frame
+    let predictive = Predictive::new().caching(false);
+    let simulated = predictive.simulated();
+    let game = game::play(&mut program, Box::new(predictive));
+
+    Solution::new(&game, &program, simulated.get())
+}
+
+
+// paddle at (2, 4) and the ball at (0, 0), then the ball moves to (1, 1) and (3, 3)
+// with a joystick input before every move
+#[cfg(test)]
+fn test_program() -> Program {
+    Program::from_opcodes(vec![
+        104, 2, 104, 4, 104, 3,
+        104, 0, 104, 0, 104, 4,
+        3, 100,
+        104, 0, 104, 0, 104, 0,
+        104, 1, 104, 1, 104, 4,
+        3, 100,
+        104, 1, 104, 1, 104, 0,
+        104, 3, 104, 3, 104, 4,
+        3, 100,
+        99
+    ])
+}
+
+#[test]
+fn test_predict_landing() {
+    let program = test_program();
+    assert_eq!(predict_landing(&program, 0), (Some(0), 6));
+    assert_eq!(predict_landing(&program, 1), (Some(1), 6 + 1 + 6));
+    assert_eq!(predict_landing(&program, 3), (Some(3), 6 + 1 + 6 + 1 + 6));
+    // the simulation ends with the program
+    assert_eq!(predict_landing(&program, 5), (None, 6 + 2 * (1 + 6) + 1 + 1));
+}
+
+// position of the snapshot, landing and simulated instructions
+#[cfg(test)]
+type Prediction = (usize, Option<i32>, u64);
+
+// predicts on every input and remembers where the program stood
+#[cfg(test)]
+struct SnapshotProbe {
+    predictions: Rc<RefCell<Vec<Prediction>>>
+}
+
+#[cfg(test)]
+impl Controller for SnapshotProbe {
+    fn input(&mut self, _game: &Game, program: &Program) -> i64 {
+        let snapshot = program.snapshot();
+        let (landing, executed) = predict_landing(program, 3);
+        self.predictions.borrow_mut().push((snapshot.position, landing, executed));
+        0
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    let predictions = Rc::new(RefCell::new(Vec::new()));
+    let mut program = test_program();
+    game::play(&mut program, Box::new(SnapshotProbe { predictions: predictions.clone() }));
+
+    // every simulation starts over with the input instruction itself, not its parameter
+    assert_eq!(*predictions.borrow(), vec![
+        (12, Some(3), 1 + 6 + 1 + 6),
+        (26, Some(3), 1 + 6),
+        (40, None, 1 + 1)
+    ]);
+}
+
+#[test]
+fn test_predictive() {
+    let play = |predictive: Predictive| {
+        let simulated = predictive.simulated();
+        let game = game::play(&mut test_program(), Box::new(predictive));
+        (game.inputs, simulated.get())
+    };
+
+    // the ball lands right of the paddle, the last input is the bounce itself
+    assert_eq!(play(Predictive::new()), (vec![1, 1, 1], 1 + 6 + 1 + 6));
+    assert_eq!(play(Predictive::new().caching(false)), (vec![1, 1, 1], (1 + 6 + 1 + 6) + (1 + 6)));
+
+    let solution = solve(test_program());
+    assert_eq!((solution.inputs, solution.instructions, solution.simulated_instructions), (3, 6 + 2 * (1 + 6) + 1 + 1, 21));
+}
@@ -0,0 +1,171 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use common::grid::{offset, Grid, Position, NEIGHBOURS_4};
+use common::robot;
+use common::robot::{Controller, Direction, Robot, RobotIoHandler};
+use {Program, Tile};
+
+
+// the order unexplored directions are tried in
+static DIRECTIONS: [Direction; 4] = [
+    Direction::North, Direction::South, Direction::West, Direction::East
+];
+
+fn movement_command(direction: Direction) -> i64 {
+    match direction {
+        Direction::North => 1,
+        Direction::South => 2,
+        Direction::West => 3,
+        Direction::East => 4
+    }
+}
+
+
+// fully explored area around the start position of the repair bot
+#[derive(Debug, Clone)]
+pub struct Maze {
+    pub tiles: Grid<Tile>,
+    pub start: Position
+}
+
+impl Maze {
+    pub fn oxygen(&self) -> Option<Position> {
+        self.tiles.positions_of(Tile::Oxygen).next()
+    }
+
+    pub fn is_open(&self, position: Position) -> bool {
+        self.tiles.get(position).is_some_and(|tile| !tile.is_solid())
+    }
+
+    pub fn neighbours<'a>(&'a self, position: Position) -> impl Iterator<Item=Position> + 'a {
+        NEIGHBOURS_4.iter()
+            .map(move |delta| offset(position, *delta))
+            .filter(move |next| self.is_open(*next))
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
+    Explore(Direction),
+    Backtrack(Direction)
+}
+
+// depth first search, walks back along its own path once everything around it is known
+struct Explorer {
+    // directions taken from the start to the current position
+    path: Vec<Direction>,
+    pending: Option<Move>,
+    maze: Rc<RefCell<Option<Maze>>>
+}
+
+impl Explorer {
+    fn next_move(&self, robot: &Robot<Tile>) -> Option<Move> {
+        let unknown = DIRECTIONS.iter()
+            .find(|direction| !robot.tiles.contains(direction.add_delta(robot.position)));
+
+        match unknown {
+            Some(direction) => Some(Move::Explore(*direction)),
+            None => self.path.last().map(|direction| Move::Backtrack(direction.reverse()))
+        }
+    }
+}
+
+impl Controller<Tile> for Explorer {
+    fn input(&mut self, robot: &mut Robot<Tile>) -> i64 {
+        let next = self.next_move(robot).expect("exploration already finished");
+        robot.direction = match next {
+            Move::Explore(direction) | Move::Backtrack(direction) => direction
+        };
+        self.pending = Some(next);
+
+        movement_command(robot.direction)
+    }
+
+    fn output(&mut self, robot: &mut Robot<Tile>, values: &[i64]) {
+        let tile = <Tile as robot::Tile>::from_number(values[0]).expect("invalid output");
+        let next = robot.ahead();
+
+        robot.tiles.insert(next, tile);
+        if !tile.is_solid() {
+            robot.forward();
+            match self.pending.take() {
+                Some(Move::Explore(direction)) => self.path.push(direction),
+                Some(Move::Backtrack(_)) => { self.path.pop(); },
+                None => ()
+            }
+        }
+
+        if self.next_move(robot).is_none() {
+            *self.maze.borrow_mut() = Some(Maze {
+                tiles: robot.tiles.clone(),
+                start: robot.position
+            });
+        }
+    }
+
+    fn is_finished(&self, _robot: &Robot<Tile>) -> bool {
+        self.maze.borrow().is_some()
+    }
+}
+
+
+// maps every tile reachable from `start`, None if the program exits before that
+pub fn explore(program: &mut Program, start: Position) -> Option<Maze> {
+    let maze = Rc::new(RefCell::new(None));
+
+    let mut robot = Robot::new(start, Direction::North);
+    robot.paint(Tile::Empty);
+
+    let explorer = Explorer {
+        path: Vec::new(),
+        pending: None,
+        maze: maze.clone()
+    };
+    program.set_io_handler(Box::new(RobotIoHandler::new(robot, explorer)));
+    program.run();
+
+    let result = maze.borrow_mut().take();
+    result
+}
+
+
+#[test]
+fn test_explore() {
+    let layout = Grid::parse("\
+        #######\n\
+        #...#.#\n\
+        #.#.#.#\n\
+        #.#...#\n\
+        #.###O#\n\
+        #.....#\n\
+        #######", |c| match c {
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Empty),
+            'O' => Some(Tile::Oxygen),
+            _ => None
+        });
+
+    for &start in &[(1, 1), (5, 1), (3, 3)] {
+        let maze = Rc::new(RefCell::new(None));
+        let mut explorer = Explorer { path: Vec::new(), pending: None, maze: maze.clone() };
+        let mut robot = Robot::new(start, Direction::North);
+        robot.paint(Tile::Empty);
+
+        while !explorer.is_finished(&robot) {
+            explorer.input(&mut robot);
+            // the layout is parsed with y growing downwards, the robot has north going up
+            let (x, y) = robot.ahead();
+            let tile = layout.get((x, 2 * start.1 - y)).expect("left the layout");
+            explorer.output(&mut robot, &[robot::Tile::to_number(tile)]);
+        }
+
+        let maze = maze.borrow_mut().take().unwrap();
+        assert_eq!(maze.start, start);
+        assert_eq!(robot.position, start);
+        assert_eq!(maze.oxygen(), Some((5, 2 * start.1 - 4)));
+        // the corners and the wall above the one splitting the top row are never seen
+        assert_eq!(maze.tiles.len(), layout.len() - 5);
+    }
+}
@@ -0,0 +1,163 @@
+use std::io;
+use std::fs;
+use std::thread;
+use std::path::Path;
+use std::num::NonZeroU32;
+use std::time::Duration;
+use std::collections::HashSet;
+
+use common::grid::{Grid, Position};
+use common::export::{Palette, Raster, BLACK, WHITE};
+use explore::Maze;
+use Tile;
+
+
+// fills the open tiles of a maze one minute at a time, every step yields the newly filled tiles
+#[derive(Debug, Clone)]
+pub struct Flood<'a> {
+    maze: &'a Maze,
+    blocked: HashSet<Position>,
+    filled: HashSet<Position>,
+    frontier: Vec<Position>,
+    minute: usize
+}
+
+impl<'a> Flood<'a> {
+    // sources on walls or outside of the maze are ignored
+    pub fn new<I: IntoIterator<Item=Position>>(maze: &'a Maze, sources: I) -> Self {
+        let mut flood = Flood {
+            maze,
+            blocked: HashSet::new(),
+            filled: HashSet::new(),
+            frontier: Vec::new(),
+            minute: 0
+        };
+        for source in sources {
+            if maze.is_open(source) && flood.filled.insert(source) {
+                flood.frontier.push(source);
+            }
+        }
+        flood
+    }
+
+    // the flood never enters a blocked tile, a blocked source stays filled but does not spread
+    pub fn block(mut self, position: Position) -> Self {
+        self.blocked.insert(position);
+        self.frontier.retain(|source| *source != position);
+        self
+    }
+
+    pub fn minute(&self) -> usize {
+        self.minute
+    }
+
+    // the tiles of the maze with every filled tile turned into oxygen
+    pub fn snapshot(&self) -> Grid<Tile> {
+        let mut tiles = self.maze.tiles.clone();
+        for position in &self.filled {
+            tiles.insert(*position, Tile::Oxygen);
+        }
+        tiles
+    }
+}
+
+impl<'a> Iterator for Flood<'a> {
+    type Item = Vec<Position>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut filled = Vec::new();
+        for position in &self.frontier {
+            for next in self.maze.neighbours(*position) {
+                if !self.blocked.contains(&next) && self.filled.insert(next) {
+                    filled.push(next);
+                }
+            }
+        }
+
+        if filled.is_empty() {
+            self.frontier.clear();
+            return None;
+        }
+
+        self.minute += 1;
+        self.frontier = filled.clone();
+        Some(filled)
+    }
+}
+
+
+// draws every minute of the flood in the terminal
+pub fn animate(mut flood: Flood, frame_rate: NonZeroU32) {
+    let frame = Duration::from_secs(1) / frame_rate.get();
+    loop {
+        let maze = Maze { tiles: flood.snapshot(), start: flood.maze.start };
+        print!("\x1b[2J\x1b[H{}\r\nminute {}\r\n", maze, flood.minute());
+        thread::sleep(frame);
+
+        if flood.next().is_none() {
+            break;
+        }
+    }
+}
+
+// writes one image per minute into `directory`, named by the minute
+pub fn export_frames<P: AsRef<Path>>(mut flood: Flood, directory: P) -> io::Result<usize> {
+    let palette = Palette::new(BLACK)
+        .with(Tile::Wall, (128, 128, 128))
+        .with(Tile::Empty, WHITE)
+        .with(Tile::Oxygen, (64, 128, 255));
+
+    fs::create_dir_all(&directory)?;
+    let mut frames = 0;
+    loop {
+        // north is up, images have y growing downwards
+        let raster = Raster::from_grid(&flood.snapshot().flip_vertical(), &palette, 4);
+        raster.save(directory.as_ref().join(format!("minute-{:04}.png", flood.minute())))?;
+        frames += 1;
+
+        if flood.next().is_none() {
+            break Ok(frames);
+        }
+    }
+}
+
+
+#[cfg(test)]
+fn test_maze() -> Maze {
+    let tiles = Grid::parse("\
+        ######\n\
+        #..###\n\
+        #.#..#\n\
+        #.O.#\n\
+        #####", |c| match c {
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Empty),
+            'O' => Some(Tile::Oxygen),
+            _ => None
+        });
+    Maze { tiles, start: (1, 1) }
+}
+
+#[test]
+fn test_flood() {
+    let maze = test_maze();
+
+    let mut flood = Flood::new(&maze, maze.oxygen());
+    assert_eq!(flood.next().map(|mut x| { x.sort(); x }), Some(vec![(1, 3), (3, 3)]));
+    assert_eq!(flood.next().map(|mut x| { x.sort(); x }), Some(vec![(1, 2), (3, 2)]));
+    assert_eq!(flood.minute(), 2);
+    assert_eq!(flood.snapshot().get((3, 2)), Some(&Tile::Oxygen));
+    assert_eq!(flood.count(), 2);
+
+    let flood = Flood::new(&maze, maze.oxygen());
+    assert_eq!(flood.count(), 4);
+
+    // the source on the wall is ignored
+    let flood = Flood::new(&maze, vec![(2, 3), (2, 1), (0, 0)]);
+    assert_eq!(flood.count(), 3);
+
+    let mut flood = Flood::new(&maze, maze.oxygen()).block((3, 3));
+    assert_eq!(flood.by_ref().count(), 4);
+    assert_eq!(flood.snapshot().positions_of(Tile::Oxygen).count(), 5);
+    assert_eq!(flood.snapshot().get((4, 2)), Some(&Tile::Empty));
+}
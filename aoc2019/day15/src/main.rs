@@ -1,10 +1,20 @@
+extern crate common;
 use std::io;
 use std::num;
 use std::fmt;
-use std::cmp;
 use std::fs::File;
 use std::io::Read;
-use std::collections::{HashSet, HashMap};
+use std::collections::HashMap;
+
+mod explore;
+mod flood;
+
+use common::args::Args;
+use common::grid::{Position, YAxis};
+use common::intcode::IoHandler;
+use common::pathfinding;
+use explore::Maze;
+use flood::Flood;
 
 
 #[derive(Debug)]
@@ -86,6 +96,10 @@ impl Program {
         while self.is_running() {
             let opcode = OpCode::read(self);
             result = opcode.execute(self).or(result);
+
+            if self.io_handler.as_ref().is_some_and(|io_handler| io_handler.wants_pause()) {
+                self.pause();
+            }
         }
 
         if self.is_done() { RunResult::Done(result) } else { RunResult::Paused(result) }
@@ -311,14 +325,6 @@ impl ParamMode {
 }
 
 
-trait IoHandler {
-    fn input(&mut self) -> i64;
-    fn output(&mut self, value: i64);
-
-    fn done(&mut self) {
-    }
-}
-
 struct StdInOutIoHandler {
 }
 
@@ -363,7 +369,6 @@ impl FixedIoHandler {
 }
 
 
-
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Tile {
     Wall,
@@ -371,274 +376,112 @@ enum Tile {
     Oxygen
 }
 
-impl Tile {
-    fn from_number(tile: i64) -> Self {
+impl common::robot::Tile for Tile {
+    fn from_number(tile: i64) -> Option<Self> {
         match tile {
-            0 => Tile::Wall,
-            1 => Tile::Empty,
-            2 => Tile::Oxygen,
-            _ => panic!("invalid output")
+            0 => Some(Tile::Wall),
+            1 => Some(Tile::Empty),
+            2 => Some(Tile::Oxygen),
+            _ => None
         }
     }
 
-    fn is_solid(&self) -> bool {
+    fn to_number(&self) -> i64 {
         match self {
-            Tile::Wall => true,
-            _ => false
+            Tile::Wall => 0,
+            Tile::Empty => 1,
+            Tile::Oxygen => 2
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Direction {
-    North = 1,
-    South = 2,
-    West = 3,
-    East = 4
-}
-
-impl Direction {
-    fn delta(&self) -> (i32, i32) {
+impl Tile {
+    fn is_solid(&self) -> bool {
         match self {
-            Direction::North => (0, 1),
-            Direction::South => (0, -1),
-            Direction::West => (-1, 0),
-            Direction::East => (1, 0)
+            Tile::Wall => true,
+            _ => false
         }
     }
-
-    fn add_delta(&self, position: (i32, i32)) -> (i32, i32) {
-        let delta = self.delta();
-        (position.0 + delta.0, position.1 + delta.1)
-    }
-
-    fn all() -> impl Iterator<Item=&'static Direction> {
-        static DIRECTIONS: [Direction;  4] = [
-            Direction::North, Direction::South, Direction::West, Direction::East
-        ];
-        DIRECTIONS.into_iter()
-    }
-}
-
-
-#[derive(Debug)]
-struct RepairBotIoHandler {
-    step: i32,
-    done: bool,
-    position: (i32, i32),
-    direction: Direction,
-    tiles: HashMap<(i32, i32), (i32, Tile)>
 }
 
-impl RepairBotIoHandler {
-    fn new() -> Self {
-        RepairBotIoHandler {
-            step: 0,
-            done: false,
-            position: (0, 0),
-            direction: Direction::North,
-            tiles: HashMap::new()
-        }
-    }
 
-    fn has_visited(&self, direction: &Direction) -> bool {
-        let x = direction.add_delta(self.position);
-        self.tiles.contains_key(&x)
-    }
+fn find_shortest(maze: &Maze) -> Option<i64> {
+    let destination = maze.oxygen()?;
+    let distance = |position: &Position| {
+        (position.0 - destination.0).unsigned_abs() as u64 + (position.1 - destination.1).unsigned_abs() as u64
+    };
 
-    fn find_shortest(&self) -> Option<i64> {
-        if !self.done {
-            return None;
-        }
+    pathfinding::astar(
+        maze.start,
+        &destination,
+        |position| maze.neighbours(*position).map(|next| (next, 1)),
+        distance
+    ).map(|path| path.cost as i64)
+}
 
-        let destination = self.tiles.iter()
-            .filter(|(_, (_, tile))| tile == &Tile::Oxygen)
-            .map(|(position, _)| position)
-            .nth(0)
-            .unwrap();
-
-        let mut checked = HashSet::new();
-        let mut candidates = HashMap::new();
-
-        let est_cost = |a: (i32, i32)| -> i64 {
-            (a.0 - destination.0).abs() as i64 + (a.1 - destination.1).abs() as i64
-        };
-        candidates.insert((0, 0), (0, est_cost((0, 0))));
-
-        // A*
-        loop {
-            let next = candidates.iter().min_by_key(|(_, (g, h))| g + h);
-
-            let current = if let Some((current, _)) =  next {
-                current.clone()
-            } else {
-                break None;
-            };
-            let (cost, est) = candidates.remove(&current).unwrap();
-
-            if current == *destination {
-                break Some(cost);
-            }
+// minutes until the oxygen reached the tile furthest away from it
+fn oxygen_spread(maze: &Maze) -> i64 {
+    let start = maze.oxygen().unwrap();
 
-            checked.insert(current);
-
-            let more_candidates = Direction::all()
-                .map(|direction| direction.add_delta(current))
-                // only allow valid paths
-                .filter(|new_pos| {
-                    self.tiles.get(new_pos)
-                        .map(|(_, tile)| !tile.is_solid())
-                        .unwrap_or(false)
-                })
-                // no duplicates
-                .filter(|new_pos| !checked.contains(new_pos))
-                .map(|new_pos| (new_pos, (cost + 1, est_cost(new_pos))))
-                // if a candidate with shorter path exists, don't overwrite it
-                .filter(|(new_pos, (cost, _est))| candidates
-                        .get(new_pos)
-                        .map(|(g, _h)| cost >= g)
-                        .unwrap_or(true)
-                )
-                .collect::<Vec<_>>();
-            candidates.extend(more_candidates);
-        }
-    }
+    pathfinding::bfs(start, |position| maze.neighbours(*position))
+        .furthest()
+        .map(|(_, distance)| distance as i64)
+        .unwrap_or(0)
+}
 
-    fn oxygen_spread(&self) -> i64 {
-        let start = self.tiles.iter()
-            .filter(|(_, (_, tile))| tile == &Tile::Oxygen)
-            .map(|(position, _)| position)
-            .nth(0)
-            .unwrap();
-
-        let mut nodes = vec![(start.clone(), 0)];
-        let mut checked = HashSet::new();
-        let mut longest = 0;
-
-        loop {
-            let (current, duration) = if let Some(x) = nodes.pop() {
-                x
-            } else {
-                break longest;
-            };
-
-            checked.insert(current);
-
-            let neighbours: Vec<((i32, i32), i64)> = Direction::all()
-                .map(|direction| (direction.add_delta(current), duration + 1))
-                .filter(|(new_pos, _)| !checked.contains(new_pos))
-                .filter(|(new_pos, _)| {
-                    self.tiles.get(new_pos)
-                        .map(|(_, tile)| !tile.is_solid())
-                        .unwrap_or(false)
-                })
-                .collect();
-
-            if neighbours.len() == 0 {
-                // dead end
-                longest = cmp::max(longest, duration);
-            } else {
-                nodes.extend(neighbours);
-            }
-        }
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let maze = self.tiles.renderer()
+            .y_axis(YAxis::Up)
+            .highlight(self.start, '0')
+            .line_ending("\r\n")
+            .render(|tile| match tile {
+                Some(Tile::Empty) => '.',
+                Some(Tile::Wall) => '\u{2588}',
+                Some(Tile::Oxygen) => 'X',
+                None => ' '
+            });
+        f.write_str(&maze)
     }
 }
 
-impl IoHandler for RepairBotIoHandler {
-    fn input(&mut self) -> i64 {
-        let open = Direction::all()
-            .filter(|direction| !self.has_visited(direction))
-            .nth(0);
 
-        self.direction = if let Some(direction) = open {
-            *direction
-        } else {
-            *Direction::all()
-                .min_by_key(|direction| {
-                    self.tiles.get(&direction.add_delta(self.position)).unwrap().0
-                })
-            .expect("stuck")
-        };
+fn main() {
+    let mut program = Program::from_file(&mut File::open("../input.txt").unwrap()).unwrap();
 
-        let next = self.direction.add_delta(self.position);
-        if next == (0, 0) {
-            self.done = true;
-            println!("\n{}\n", self);
+    let maze = explore::explore(&mut program, (0, 0)).expect("program exited before the maze was mapped");
+    println!("{}\n", maze);
 
-            let shortest = self.find_shortest();
-            println!("shortest: {}", shortest.expect("no shortest path"));
+    let shortest = find_shortest(&maze);
+    println!("shortest: {}", shortest.expect("no shortest path"));
 
-            let longest = self.oxygen_spread();
-            println!("oxygen takes {} minutes to spread", longest);
+    let longest = oxygen_spread(&maze);
+    println!("oxygen takes {} minutes to spread", longest);
 
-            panic!("done"); // I am lazy
-        }
+    // day15 [--animate] [--fps N] [--frames DIRECTORY] [--source X,Y]... [--block X,Y]...
+    let args = Args::from_env();
+    let positions = |name: &str| -> Vec<Position> {
+        args.options(name).into_iter()
+            .map(|value| {
+                let mut coordinates = value.split(',').map(|x| x.trim().parse::<i32>().expect("invalid position"));
+                (coordinates.next().expect("invalid position"), coordinates.next().expect("invalid position"))
+            })
+            .collect()
+    };
 
-        self.direction as i64
+    let mut sources = positions("--source");
+    if sources.is_empty() {
+        sources.extend(maze.oxygen());
     }
+    let flood = positions("--block").into_iter()
+        .fold(Flood::new(&maze, sources), |flood, position| flood.block(position));
 
-    fn output(&mut self, value: i64) {
-        let tile = Tile::from_number(value);
-        let next = self.direction.add_delta(self.position);
-
-        self.tiles.insert(next, (self.step, tile));
-        self.step += 1;
-
-        if !tile.is_solid() {
-            self.position = next;
-        }
+    if args.flag("--animate") {
+        let frame_rate = args.frame_rate().unwrap_or(num::NonZeroU32::new(20).unwrap());
+        flood::animate(flood.clone(), frame_rate);
     }
-
-    fn done(&mut self) {
-        println!("{}\n", self);
+    if let Some(directory) = args.option("--frames") {
+        let frames = flood::export_frames(flood.clone(), directory).unwrap();
+        println!("exported {} frames to {}", frames, directory);
     }
 }
-
-
-impl fmt::Display for RepairBotIoHandler {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut min = (0, 0);
-        let mut max = (0, 0);
-        for position in self.tiles.keys() {
-            min = (cmp::min(position.0, min.0), cmp::min(position.1, min.1));
-            max = (cmp::max(position.0, max.0), cmp::max(position.1, max.1));
-        }
-        let width = max.0 - min.0 + 1;
-        let height = max.1 - min.1 + 1;
-
-        let mut rows = Vec::new();
-        for y in (0..height).rev() {
-            let mut row = Vec::new();
-            for x in 0..width {
-                let position = (min.0 + x, min.1 + y);
-                let mut value = " ";
-                if let Some((_, tile)) = self.tiles.get(&position) {
-                    value = match tile {
-                        Tile::Empty=> ".",
-                        Tile::Wall => "\u{2588}",
-                        Tile::Oxygen => "X"
-                    }
-                }
-                if position == (0, 0) {
-                    value = "0";
-                }
-                if position == self.position {
-                    value = "R";
-                }
-                row.push(value);
-            }
-            rows.push(row.join(""));
-        }
-
-        write!(f, "{}", rows.join("\r\n"))
-    }
-}
-
-
-fn main() {
-    let mut program = Program::from_file(&mut File::open("../input.txt").unwrap()).unwrap();
-    program.set_io_handler(Box::new(RepairBotIoHandler::new()));
-
-    program.run();
-}
-